@@ -0,0 +1,136 @@
+use crate::interval::{self, Interval};
+use crate::{Point3, Ray};
+
+/// Axis-aligned bounding box, stored as one interval per axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+pub const EMPTY: Aabb = Aabb::new(interval::EMPTY, interval::EMPTY, interval::EMPTY);
+
+impl Default for Aabb {
+    fn default() -> Self {
+        EMPTY
+    }
+}
+
+impl Aabb {
+    pub const fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Creates the box spanned by two opposite corners, in any order.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self {
+            x: Interval::new(a.x.min(b.x), a.x.max(b.x)),
+            y: Interval::new(a.y.min(b.y), a.y.max(b.y)),
+            z: Interval::new(a.z.min(b.z), a.z.max(b.z)),
+        }
+    }
+
+    /// The smallest box containing both given boxes.
+    pub fn enclosing(a: Aabb, b: Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(a.x, b.x),
+            y: Interval::enclosing(a.y, b.y),
+            z: Interval::enclosing(a.z, b.z),
+        }
+    }
+
+    /// Returns the interval of the given axis (0 = x, 1 = y, 2 = z).
+    pub fn axis(&self, n: usize) -> Interval {
+        match n {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty() || self.y.is_empty() || self.z.is_empty()
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        if x > y && x > z {
+            0
+        } else if y > z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    /// Center of the box. Unbounded axes are treated as centered at zero.
+    pub fn centroid(&self) -> Point3 {
+        let center = |axis: Interval| {
+            let c = 0.5 * (axis.min + axis.max);
+            if c.is_finite() {
+                c
+            } else {
+                0.0
+            }
+        };
+        Point3::new(center(self.x), center(self.y), center(self.z))
+    }
+
+    /// Pads axes thinner than 'delta' so that flat objects still get a box
+    /// with some volume.
+    pub fn padded(self, delta: f32) -> Self {
+        let pad = |axis: Interval| match axis.size() < delta {
+            true => axis.expanded(delta),
+            false => axis,
+        };
+        Self::new(pad(self.x), pad(self.y), pad(self.z))
+    }
+
+    /// Slab test: checks whether the ray passes through the box for some t
+    /// inside of 'allowed_t'.
+    pub fn hit(&self, ray: &Ray, allowed_t: Interval) -> bool {
+        self.clip(ray, allowed_t).is_some()
+    }
+
+    /// The part of 'allowed_t' in which the ray is inside the box, or None
+    /// if it misses the box.
+    pub fn clip(&self, ray: &Ray, mut allowed_t: Interval) -> Option<Interval> {
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let origin = [origin.x, origin.y, origin.z];
+        let direction = [direction.x, direction.y, direction.z];
+
+        for (n, (origin, direction)) in origin.into_iter().zip(direction).enumerate() {
+            let axis = self.axis(n);
+            let inverse_direction = 1.0 / direction;
+
+            let t0 = (axis.min - origin) * inverse_direction;
+            let t1 = (axis.max - origin) * inverse_direction;
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            // NaN (a ray lying exactly in a slab boundary) leaves the
+            // interval unchanged instead of rejecting the box
+            if t0 > allowed_t.min {
+                allowed_t.min = t0;
+            }
+            if t1 < allowed_t.max {
+                allowed_t.max = t1;
+            }
+
+            if allowed_t.max <= allowed_t.min {
+                return None;
+            }
+        }
+
+        Some(allowed_t)
+    }
+}
@@ -0,0 +1,63 @@
+mod distribution;
+mod environment_map;
+mod sky;
+
+pub use environment_map::EnvironmentMap;
+pub use sky::{Sky, SUN_SIZE};
+
+use crate::{Color, Ray, Vec3};
+
+/// What rays that don't hit any object see, i.e. the light arriving from
+/// infinitely far away.
+pub trait Background {
+    fn color(&self, ray: &Ray) -> Color;
+
+    /// Whether 'random_direction' favors bright directions, so the renderer
+    /// can sample the background like the lights.
+    fn is_importance_sampled(&self) -> bool {
+        false
+    }
+
+    /// Density, per unit solid angle, with which 'random_direction' picks
+    /// 'direction'.
+    fn pdf_value(&self, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    fn random_direction(&self) -> Vec3 {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+}
+
+/// Vertical white to blue gradient, like a bright sky.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gradient;
+
+impl Background for Gradient {
+    fn color(&self, ray: &Ray) -> Color {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let blue = Color::new(0.5, 0.7, 1.0);
+        let direction = ray.direction().normalized();
+        let lerp_factor = 0.5 * (direction.y + 1.0);
+        (1.0 - lerp_factor) * white + lerp_factor * blue
+    }
+}
+
+/// The same color in every direction, e.g. black for scenes that are only
+/// lit by emissive materials.
+#[derive(Debug, Clone, Copy)]
+pub struct Solid {
+    color: Color,
+}
+
+impl Solid {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for Solid {
+    fn color(&self, _ray: &Ray) -> Color {
+        self.color
+    }
+}
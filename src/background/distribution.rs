@@ -0,0 +1,68 @@
+/// Piecewise constant distribution over 0..1, with each of the segments
+/// being picked proportionally to its value.
+pub struct Distribution {
+    function: Vec<f32>,
+    /// Cumulative probabilities at the segment boundaries, from 0 to 1
+    cdf: Vec<f32>,
+    /// Integral of the function over 0..1
+    integral: f32,
+}
+
+impl Distribution {
+    /// 'function' needs at least one value, all of them non-negative. If
+    /// they are all 0, the segments get picked uniformly.
+    pub fn new(function: Vec<f32>) -> Self {
+        let count = function.len() as f32;
+        let mut cdf = Vec::with_capacity(function.len() + 1);
+        let mut sum = 0.0;
+        cdf.push(0.0);
+        for value in &function {
+            sum += value / count;
+            cdf.push(sum);
+        }
+
+        let integral = sum;
+        for (i, cumulative) in cdf.iter_mut().enumerate() {
+            *cumulative = match integral > 0.0 {
+                true => *cumulative / integral,
+                false => i as f32 / count,
+            };
+        }
+
+        Self {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Maps a uniform random number in 0..1 to a position in 0..1 and the
+    /// index of the segment it lies in.
+    pub fn sample(&self, xi: f32) -> (f32, usize) {
+        let count = self.function.len();
+        let index = self
+            .cdf
+            .partition_point(|&cumulative| cumulative <= xi)
+            .saturating_sub(1)
+            .min(count - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = match width > 0.0 {
+            true => ((xi - self.cdf[index]) / width).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        ((index as f32 + offset) / count as f32, index)
+    }
+
+    /// Density over 0..1 of positions in the segment 'index'.
+    pub fn pdf(&self, index: usize) -> f32 {
+        match self.integral > 0.0 {
+            true => self.function[index] / self.integral,
+            false => 1.0,
+        }
+    }
+}
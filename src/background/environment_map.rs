@@ -0,0 +1,165 @@
+use super::distribution::Distribution;
+use super::Background;
+
+use crate::reading::{self, ImageError};
+use crate::texture;
+use crate::transform::Transform;
+use crate::{random, Color, Ray, Vec3};
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// Background from an equirectangular (latitude-longitude) image. The
+/// middle of the image is seen when looking along -z, the top row straight
+/// up. Directions get sampled proportionally to their brightness, so small
+/// bright regions like the sun light the scene without much noise.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    intensity: f32,
+    /// Turns the map around the y axis
+    rotation: Transform,
+    /// Picks a row, weighted by the brightness of all its pixels
+    rows: Distribution,
+    /// Picks a pixel within each row
+    columns: Vec<Distribution>,
+}
+
+impl EnvironmentMap {
+    /// 'pixels' holds the rows from top to bottom, as linear colors.
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        assert!(
+            width > 0 && height > 0 && pixels.len() == width * height,
+            "environment map of {width}x{height} pixels needs as many colors"
+        );
+
+        // Rows close to the poles cover less solid angle, the sine of the
+        // polar angle makes up for the stretching
+        let columns: Vec<Distribution> = pixels
+            .chunks_exact(width)
+            .enumerate()
+            .map(|(row, pixels)| {
+                let sin_theta = (PI * (row as f32 + 0.5) / height as f32).sin();
+                let weights = pixels
+                    .iter()
+                    .map(|pixel| pixel.luminance().max(0.0) * sin_theta)
+                    .collect();
+                Distribution::new(weights)
+            })
+            .collect();
+        let rows = Distribution::new(columns.iter().map(Distribution::integral).collect());
+
+        Self {
+            width,
+            height,
+            pixels,
+            intensity: 1.0,
+            rotation: Transform::identity(),
+            rows,
+            columns,
+        }
+    }
+
+    /// Loads a Radiance HDR or PFM file. Low dynamic range images are taken
+    /// to be sRGB encoded and converted to linear colors.
+    pub fn load(path: &Path) -> Result<Self, ImageError> {
+        let image = reading::read_image(path)?;
+        let pixels = match image.is_linear {
+            true => image.pixels,
+            false => image
+                .pixels
+                .into_iter()
+                .map(texture::srgb_to_linear)
+                .collect(),
+        };
+        Ok(Self::new(image.width, image.height, pixels))
+    }
+
+    /// Turns the map counter clockwise around the y axis, as seen from
+    /// above.
+    pub fn with_rotation(mut self, degrees: f32) -> Self {
+        self.rotation = Transform::rotation_y(degrees);
+        self
+    }
+
+    /// Scales the brightness of the map.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Position of a world space direction on the image, with u going from
+    /// left to right and v from top to bottom.
+    fn uv(&self, direction: Vec3) -> (f32, f32) {
+        let direction = self.rotation.inverse().vector(direction).normalized();
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+        let phi = direction.x.atan2(-direction.z);
+        ((phi + PI) / (2.0 * PI), theta / PI)
+    }
+
+    fn direction(&self, u: f32, v: f32) -> Vec3 {
+        let (theta, phi) = (v * PI, u * 2.0 * PI - PI);
+        let sin_theta = theta.sin();
+        let local = Vec3::new(sin_theta * phi.sin(), theta.cos(), -sin_theta * phi.cos());
+        self.rotation.vector(local)
+    }
+
+    /// The pixel containing (u, v), as column and row.
+    fn pixel_index(&self, u: f32, v: f32) -> (usize, usize) {
+        let column = ((u * self.width as f32) as usize).min(self.width - 1);
+        let row = ((v * self.height as f32) as usize).min(self.height - 1);
+        (column, row)
+    }
+
+    /// Linear interpolation of the four closest pixels. The image wraps
+    /// around horizontally and ends at the poles.
+    fn interpolate(&self, u: f32, v: f32) -> Color {
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let pixel = |column: i64, row: i64| {
+            let column = column.rem_euclid(self.width as i64) as usize;
+            let row = row.clamp(0, self.height as i64 - 1) as usize;
+            self.pixels[row * self.width + column]
+        };
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = (1.0 - tx) * pixel(x0, y0) + tx * pixel(x0 + 1, y0);
+        let bottom = (1.0 - tx) * pixel(x0, y0 + 1) + tx * pixel(x0 + 1, y0 + 1);
+        (1.0 - ty) * top + ty * bottom
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn color(&self, ray: &Ray) -> Color {
+        let (u, v) = self.uv(ray.direction());
+        self.intensity * self.interpolate(u, v)
+    }
+
+    fn is_importance_sampled(&self) -> bool {
+        self.rows.integral() > 0.0
+    }
+
+    /// The density over the image divided by the solid angle the image's
+    /// unit square gets stretched over at this latitude.
+    fn pdf_value(&self, direction: Vec3) -> f32 {
+        let (u, v) = self.uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let (column, row) = self.pixel_index(u, v);
+        let image_pdf = self.rows.pdf(row) * self.columns[row].pdf(column);
+        image_pdf / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn random_direction(&self) -> Vec3 {
+        let (v, row) = self.rows.sample(random::random());
+        let (u, _) = self.columns[row].sample(random::random());
+        self.direction(u, v)
+    }
+}
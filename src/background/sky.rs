@@ -0,0 +1,301 @@
+use super::Background;
+
+use crate::onb::Onb;
+use crate::{random, Color, Ray, Vec3};
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// The model's luminances are in kcd/m², this brings the zenith of a clear
+/// day sky to about 1.
+const LUMINANCE_SCALE: f32 = 0.1;
+
+/// Luminance of the sun outside of the atmosphere, in kcd/m².
+const SUN_LUMINANCE: f32 = 2.0e6;
+
+/// Angular diameter of the sun as seen from the earth, in degrees.
+pub const SUN_SIZE: f32 = 0.53;
+
+/// Wavelengths in micrometers at which the red, green and blue transmittance
+/// of the atmosphere is evaluated.
+const WAVELENGTHS: [f32; 3] = [0.680, 0.550, 0.440];
+
+/// Steps of the polar angle when integrating the light the sky casts onto
+/// the ground, the azimuth gets twice as many.
+const IRRADIANCE_STEPS: usize = 32;
+
+/// Daylight sky after Preetham, Shirley and Smits, "A Practical Analytic
+/// Model for Daylight" (1999), with a sun disk. The model is meant for the
+/// sun above the horizon. Below the horizon lies a diffuse ground lit by
+/// the sky and the sun.
+pub struct Sky {
+    /// Points towards the sun
+    sun_direction: Vec3,
+    turbidity: f32,
+    /// Luminance and chromaticity x and y at the zenith
+    zenith: [f32; 3],
+    /// Coefficients A to E of the Perez distribution, for the luminance and
+    /// both chromaticities
+    perez: [[f32; 5]; 3],
+    /// Perez distribution at the zenith, which the other directions are
+    /// relative to
+    perez_zenith: [f32; 3],
+    /// Light the sky without the sun casts onto a horizontal surface
+    sky_irradiance: Color,
+    ground_albedo: Color,
+    sun: Option<Sun>,
+    intensity: f32,
+}
+
+struct Sun {
+    radiance: Color,
+    /// One minus the cosine of the angular radius, the solid angle of the
+    /// disk divided by 2 pi
+    extent: f32,
+    basis: Onb,
+}
+
+impl Sky {
+    /// 'turbidity' is the haziness of the atmosphere, from 2 for a very
+    /// clear sky to 10 for a hazy one. The sun gets the size it has seen
+    /// from the earth, and the ground an albedo of 0.3.
+    pub fn new(sun_direction: Vec3, turbidity: f32) -> Self {
+        let sun_direction = sun_direction.normalized();
+        let t = turbidity.clamp(1.7, 10.0);
+        // The model breaks down for the sun below the horizon
+        let theta_sun = sun_direction
+            .y
+            .clamp(-1.0, 1.0)
+            .acos()
+            .min(FRAC_PI_2 - 0.01);
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |rows: [[f32; 4]; 3]| {
+            let angles = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+            let turbidities = [t * t, t, 1.0];
+            rows.iter()
+                .zip(turbidities)
+                .map(|(row, factor)| {
+                    factor * row.iter().zip(angles).map(|(a, b)| a * b).sum::<f32>()
+                })
+                .sum::<f32>()
+        };
+        let x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let mut sky = Self {
+            sun_direction,
+            turbidity: t,
+            zenith: [luminance.max(0.0), x, y],
+            perez,
+            perez_zenith: perez
+                .map(|coefficients| perez_distribution(coefficients, 1.0, theta_sun)),
+            sky_irradiance: Color::new(0.0, 0.0, 0.0),
+            ground_albedo: Color::new(0.3, 0.3, 0.3),
+            sun: None,
+            intensity: 1.0,
+        };
+        sky.sky_irradiance = sky.integrate_irradiance();
+        sky.with_sun_size(SUN_SIZE)
+    }
+
+    pub fn with_ground_albedo(mut self, albedo: Color) -> Self {
+        self.ground_albedo = albedo;
+        self
+    }
+
+    /// Scales the brightness of the sky and the sun.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Sets the angular diameter of the sun disk in degrees, 0 removes it.
+    /// The sun keeps the light it gives off, so larger suns only soften the
+    /// shadows.
+    pub fn with_sun_size(mut self, size: f32) -> Self {
+        let radius = (0.5 * size).to_radians();
+        if radius <= 0.0 || self.sun_direction.y <= 0.0 {
+            self.sun = None;
+            return self;
+        }
+
+        // Precise for tiny angles, unlike 1 - cos(radius)
+        let extent = 2.0 * (0.5 * radius).sin().powi(2);
+        let natural_radius = (0.5 * SUN_SIZE).to_radians();
+        let natural_extent = 2.0 * (0.5 * natural_radius).sin().powi(2);
+
+        let transmittance = sun_transmittance(self.sun_direction, self.turbidity);
+        let radiance = LUMINANCE_SCALE * SUN_LUMINANCE * (natural_extent / extent);
+        self.sun = Some(Sun {
+            radiance: radiance * transmittance,
+            extent,
+            basis: Onb::from_w(self.sun_direction),
+        });
+        self
+    }
+
+    /// Light of the sky alone, for directions above the horizon.
+    fn sky_radiance(&self, direction: Vec3) -> Color {
+        let cos_theta = direction.y.max(1e-3);
+        let cos_gamma = Vec3::dot(direction, self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            let relative =
+                perez_distribution(self.perez[i], cos_theta, gamma) / self.perez_zenith[i];
+            self.zenith[i] * relative
+        });
+        LUMINANCE_SCALE * xyy_to_rgb(x, y, luminance)
+    }
+
+    /// Integrates the sky's light over the upper hemisphere, weighted by
+    /// the cosine towards the zenith.
+    fn integrate_irradiance(&self) -> Color {
+        let theta_step = FRAC_PI_2 / IRRADIANCE_STEPS as f32;
+        let phi_step = 2.0 * PI / (2 * IRRADIANCE_STEPS) as f32;
+
+        let mut irradiance = Color::new(0.0, 0.0, 0.0);
+        for i in 0..IRRADIANCE_STEPS {
+            let theta = (i as f32 + 0.5) * theta_step;
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            for j in 0..2 * IRRADIANCE_STEPS {
+                let phi = (j as f32 + 0.5) * phi_step;
+                let direction = Vec3::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+                let solid_angle = sin_theta * theta_step * phi_step;
+                irradiance += self.sky_radiance(direction) * (cos_theta * solid_angle);
+            }
+        }
+        irradiance
+    }
+
+    /// The ground reflects the light of the sky and the sun diffusely.
+    fn ground_radiance(&self) -> Color {
+        let mut irradiance = self.sky_irradiance;
+        if let Some(sun) = &self.sun {
+            irradiance += sun.radiance * (2.0 * PI * sun.extent * self.sun_direction.y);
+        }
+        self.ground_albedo * irradiance / PI
+    }
+}
+
+impl Background for Sky {
+    fn color(&self, ray: &Ray) -> Color {
+        let direction = ray.direction().normalized();
+        if direction.y <= 0.0 {
+            return self.intensity * self.ground_radiance();
+        }
+
+        let mut color = self.sky_radiance(direction);
+        if let Some(sun) = &self.sun {
+            if Vec3::dot(direction, self.sun_direction) >= 1.0 - sun.extent {
+                color += sun.radiance;
+            }
+        }
+        self.intensity * color
+    }
+
+    /// Only the sun gets sampled, the sky is smooth enough to be found by
+    /// scattered rays.
+    fn is_importance_sampled(&self) -> bool {
+        self.sun.is_some()
+    }
+
+    fn pdf_value(&self, direction: Vec3) -> f32 {
+        let Some(sun) = &self.sun else {
+            return 0.0;
+        };
+        let cos_angle = Vec3::dot(direction.normalized(), self.sun_direction);
+        match cos_angle >= 1.0 - sun.extent && direction.y > 0.0 {
+            true => 1.0 / (2.0 * PI * sun.extent),
+            false => 0.0,
+        }
+    }
+
+    /// Picks a direction uniformly within the sun disk.
+    fn random_direction(&self) -> Vec3 {
+        let Some(sun) = &self.sun else {
+            return self.sun_direction;
+        };
+
+        let cos_theta = 1.0 - random::random() * sun.extent;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random::random();
+        sun.basis
+            .local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+}
+
+/// Perez et al.'s luminance distribution for the coefficients A to E, at a
+/// zenith angle with the given cosine and an angle 'gamma' to the sun.
+fn perez_distribution([a, b, c, d, e]: [f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    let cos_gamma = gamma.cos();
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+/// Fraction of the sun's light that makes it through the air for red, green
+/// and blue, scattered away by molecules (Rayleigh) and haze (Ångström's
+/// formula).
+fn sun_transmittance(sun_direction: Vec3, turbidity: f32) -> Color {
+    let theta = sun_direction.y.clamp(-1.0, 1.0).acos();
+    // Kasten's relative optical mass, the amount of air the light passes
+    // through compared to straight from above
+    let optical_mass =
+        1.0 / (theta.cos() + 0.15 * (93.885 - theta.to_degrees()).max(1e-3).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let alpha = 1.3;
+
+    let [r, g, b] = WAVELENGTHS.map(|lambda| {
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * optical_mass).exp();
+        let aerosol = (-beta * lambda.powf(-alpha) * optical_mass).exp();
+        rayleigh * aerosol
+    });
+    Color::new(r, g, b)
+}
+
+/// Converts CIE xyY to linear sRGB, dropping negative components of colors
+/// outside the sRGB gamut.
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Color {
+    if y <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Color::new(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
@@ -0,0 +1,335 @@
+use crate::aabb::Aabb;
+use crate::hittable_list::HittableList;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedHittable};
+
+use std::sync::Arc;
+
+/// Number of buckets the centroids get sorted into when evaluating the
+/// surface area heuristic along an axis.
+const SAH_BUCKETS: usize = 12;
+
+/// Nodes with at most this many objects may become a leaf if splitting them
+/// is not expected to pay off.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Relative cost of traversing a node compared to intersecting one object.
+const TRAVERSAL_COST: f32 = 0.125;
+
+/// Bounding volume hierarchy over the objects of a HittableList. The
+/// hierarchy is built top-down, splitting at the bucket boundary with the
+/// lowest estimated cost according to the surface area heuristic.
+pub struct BvhNode {
+    left: SharedHittable,
+    right: Option<SharedHittable>,
+    bounding_box: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        let objects = list.objects().to_vec();
+        Self::build(objects)
+    }
+
+    fn build(mut objects: Vec<SharedHittable>) -> Self {
+        let bounding_box = objects.iter().fold(Aabb::default(), |acc, object| {
+            Aabb::enclosing(acc, object.bounding_box())
+        });
+
+        match objects.len() {
+            0 => {
+                return Self {
+                    left: Arc::new(HittableList::default()),
+                    right: None,
+                    bounding_box,
+                }
+            }
+            1 => {
+                return Self {
+                    left: objects.pop().unwrap(),
+                    right: None,
+                    bounding_box,
+                }
+            }
+            2 => {
+                let right = objects.pop();
+                return Self {
+                    left: objects.pop().unwrap(),
+                    right,
+                    bounding_box,
+                };
+            }
+            _ => {}
+        }
+
+        let right_objects = match Self::find_split(&objects, &bounding_box) {
+            Some(split) => split.partition(&mut objects),
+            None if objects.len() <= MAX_LEAF_SIZE => {
+                return Self {
+                    left: Arc::new(HittableList::new(objects)),
+                    right: None,
+                    bounding_box,
+                };
+            }
+            None => Self::split_in_middle(&mut objects, &bounding_box),
+        };
+
+        Self {
+            left: Self::subtree(objects),
+            right: Some(Self::subtree(right_objects)),
+            bounding_box,
+        }
+    }
+
+    fn subtree(objects: Vec<SharedHittable>) -> SharedHittable {
+        match objects.len() {
+            1 => Arc::clone(&objects[0]),
+            _ => Arc::new(Self::build(objects)),
+        }
+    }
+
+    /// Returns the split with the lowest surface area heuristic cost, or
+    /// None if keeping all objects in a single leaf is cheaper.
+    fn find_split(objects: &[SharedHittable], bounding_box: &Aabb) -> Option<Split> {
+        let centroid_bounds = objects.iter().fold(Aabb::default(), |acc, object| {
+            let centroid = object.bounding_box().centroid();
+            Aabb::enclosing(acc, Aabb::from_points(centroid, centroid))
+        });
+
+        let parent_area = bounding_box.surface_area();
+        let mut best: Option<Split> = None;
+
+        for axis in 0..3 {
+            let extent = centroid_bounds.axis(axis);
+            if extent.size() <= 0.0 {
+                continue;
+            }
+
+            let mut buckets = [(0usize, Aabb::default()); SAH_BUCKETS];
+            for object in objects {
+                let index = Split::bucket_index(object.bounding_box().centroid(), axis, extent);
+                buckets[index].0 += 1;
+                buckets[index].1 = Aabb::enclosing(buckets[index].1, object.bounding_box());
+            }
+
+            for boundary in 1..SAH_BUCKETS {
+                let (left_count, left_box) = Self::accumulate(&buckets[..boundary]);
+                let (right_count, right_box) = Self::accumulate(&buckets[boundary..]);
+                if left_count == 0 || right_count == 0 {
+                    continue;
+                }
+
+                let cost = TRAVERSAL_COST
+                    + (left_count as f32 * left_box.surface_area()
+                        + right_count as f32 * right_box.surface_area())
+                        / parent_area;
+
+                // Unbounded objects make every cost infinite, fall back to
+                // comparing the object counts in that case
+                let cost = match cost.is_finite() {
+                    true => cost,
+                    false => left_count.abs_diff(right_count) as f32,
+                };
+
+                if best.as_ref().is_none_or(|best| cost < best.cost) {
+                    best = Some(Split {
+                        axis,
+                        extent,
+                        boundary,
+                        cost,
+                    });
+                }
+            }
+        }
+
+        let leaf_cost = objects.len() as f32;
+        match best {
+            Some(split) if objects.len() > MAX_LEAF_SIZE || split.cost < leaf_cost => Some(split),
+            _ => None,
+        }
+    }
+
+    fn accumulate(buckets: &[(usize, Aabb)]) -> (usize, Aabb) {
+        buckets
+            .iter()
+            .fold((0, Aabb::default()), |(count, bounds), (n, bucket_box)| {
+                (count + n, Aabb::enclosing(bounds, *bucket_box))
+            })
+    }
+
+    /// Fallback for objects whose centroids can't be separated by buckets,
+    /// e.g. because they all coincide.
+    fn split_in_middle(
+        objects: &mut Vec<SharedHittable>,
+        bounding_box: &Aabb,
+    ) -> Vec<SharedHittable> {
+        let axis = bounding_box.longest_axis();
+        objects.sort_by(|a, b| {
+            let a = a.bounding_box().axis(axis).min;
+            let b = b.bounding_box().axis(axis).min;
+            a.total_cmp(&b)
+        });
+        let mid = objects.len() / 2;
+        objects.split_off(mid)
+    }
+}
+
+struct Split {
+    axis: usize,
+    extent: Interval,
+    boundary: usize,
+    cost: f32,
+}
+
+impl Split {
+    fn bucket_index(centroid: Point3, axis: usize, extent: Interval) -> usize {
+        let value = match axis {
+            0 => centroid.x,
+            1 => centroid.y,
+            _ => centroid.z,
+        };
+        let relative = (value - extent.min) / extent.size();
+        ((relative * SAH_BUCKETS as f32) as usize).min(SAH_BUCKETS - 1)
+    }
+
+    /// Moves all objects right of the split boundary out of 'objects' and
+    /// returns them.
+    fn partition(&self, objects: &mut Vec<SharedHittable>) -> Vec<SharedHittable> {
+        let (left, right) = objects.drain(..).partition(|object| {
+            let centroid = object.bounding_box().centroid();
+            Self::bucket_index(centroid, self.axis, self.extent) < self.boundary
+        });
+        *objects = left;
+        right
+    }
+}
+
+impl BvhNode {
+    /// Finds the closest of the hits that 'hit' reports for the children,
+    /// skipping them if the ray misses the box.
+    fn closest_hit(
+        &self,
+        ray: &Ray,
+        allowed_t: Interval,
+        hit_rec: &mut HitRecord,
+        hit: impl Fn(&SharedHittable, Interval, &mut HitRecord) -> bool,
+    ) -> bool {
+        if !self.bounding_box.hit(ray, allowed_t) {
+            return false;
+        }
+
+        let hit_left = hit(&self.left, allowed_t, hit_rec);
+
+        let hit_right = match &self.right {
+            Some(right) => {
+                let max_t = if hit_left { hit_rec.t } else { allowed_t.max };
+                hit(right, Interval::new(allowed_t.min, max_t), hit_rec)
+            }
+            None => false,
+        };
+
+        hit_left || hit_right
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        self.closest_hit(ray, allowed_t, hit_rec, |child, allowed_t, hit_rec| {
+            child.hit_mutating(ray, allowed_t, hit_rec)
+        })
+    }
+
+    fn shadow_hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        self.closest_hit(ray, allowed_t, hit_rec, |child, allowed_t, hit_rec| {
+            child.shadow_hit_mutating(ray, allowed_t, hit_rec)
+        })
+    }
+
+    fn transmittance(&self, ray: &Ray, allowed_t: Interval) -> f32 {
+        if !self.bounding_box.hit(ray, allowed_t) {
+            return 1.0;
+        }
+
+        let right = match &self.right {
+            Some(right) => right.transmittance(ray, allowed_t),
+            None => 1.0,
+        };
+        self.left.transmittance(ray, allowed_t) * right
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plane::Plane;
+    use crate::random;
+    use crate::sphere::Sphere;
+    use crate::{Color, Lambertian, SharedMaterial, Vec3};
+
+    use rand::Rng;
+
+    #[test]
+    fn finds_the_same_hits_as_a_list() {
+        let mut rng = random::generator(7);
+        let mut point = |size: f32| {
+            Point3::new(
+                rng.gen_range(-size..size),
+                rng.gen_range(-size..size),
+                rng.gen_range(-size..size),
+            )
+        };
+
+        // Every object gets a material of its own to tell them apart
+        let material =
+            || -> SharedMaterial { Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))) };
+        let mut list = HittableList::default();
+        for i in 0..40 {
+            let radius = 0.2 + (i % 5) as f32 * 0.3;
+            list.add(Arc::new(Sphere::new(point(10.0), radius, material())));
+        }
+        // Unbounded objects and ones sharing a centroid
+        list.add(Arc::new(Plane::new(
+            Point3::new(0.0, -8.0, 0.0),
+            Vec3::new(0.1, 1.0, 0.0),
+            material(),
+        )));
+        for _ in 0..3 {
+            list.add(Arc::new(Sphere::new(
+                Point3::new(2.0, 2.0, 2.0),
+                0.5,
+                material(),
+            )));
+        }
+        let bvh = BvhNode::new(list.clone());
+
+        let allowed_t = Interval::new(0.001, f32::INFINITY);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let origin = point(15.0);
+            let direction = point(1.0) - Point3::new(0.0, 0.0, 0.0);
+            let ray = Ray::new(origin, direction);
+
+            match (list.hit(&ray, allowed_t), bvh.hit(&ray, allowed_t)) {
+                (None, None) => {}
+                (Some(expected), Some(found)) => {
+                    hits += 1;
+                    assert_eq!(expected.t, found.t);
+                    assert!(Arc::ptr_eq(
+                        expected.material.as_ref().unwrap(),
+                        found.material.as_ref().unwrap()
+                    ));
+                }
+                (expected, found) => panic!(
+                    "the list hits at {:?}, the hierarchy at {:?}",
+                    expected.map(|hit| hit.t),
+                    found.map(|hit| hit.t)
+                ),
+            }
+        }
+        // Enough of the rays hit something to make the comparison count
+        assert!(hits > 500, "only {hits} rays hit");
+    }
+}
@@ -70,6 +70,22 @@ impl Viewport {
     }
 }
 
+/// The interval during which the image is exposed.
+#[derive(Clone)]
+struct Shutter {
+    open: f32,
+    close: f32,
+}
+
+impl Shutter {
+    fn sample_time(&self) -> f32 {
+        match self.close > self.open {
+            true => random_range(self.open, self.close),
+            false => self.open,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Camera {
     position: Point3,
@@ -82,6 +98,7 @@ pub struct Camera {
     defocus: Defocus,
     image: Image,
     viewport: Viewport,
+    shutter: Shutter,
 }
 
 impl Camera {
@@ -94,7 +111,7 @@ impl Camera {
         };
         let direction = pixel_sample - origin;
 
-        Ray::new(origin, direction)
+        Ray::with_time(origin, direction, self.shutter.sample_time())
     }
 
     pub fn image_width(&self) -> u32 {
@@ -1,4 +1,4 @@
-use super::{Basis, Camera, Defocus, Image, Orientation, Viewport};
+use super::{Basis, Camera, Defocus, Image, Orientation, Shutter, Viewport};
 
 use crate::{Point3, Vec3};
 
@@ -11,6 +11,11 @@ pub struct CameraBuilder {
     pub view_up: Vec3,
     pub focus_distance: f32,
     pub defocus_angle: f32,
+    /// Rays get random times between shutter_open and shutter_close, which
+    /// blurs objects moving in that interval. Without an interval, all rays
+    /// are sent at shutter_open.
+    pub shutter_open: f32,
+    pub shutter_close: f32,
 }
 
 impl Default for CameraBuilder {
@@ -24,6 +29,8 @@ impl Default for CameraBuilder {
             view_up: Vec3::new(0.0, 1.0, 0.0),
             focus_distance: 1.0,
             defocus_angle: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 }
@@ -48,6 +55,10 @@ impl CameraBuilder {
             defocus,
             image,
             viewport,
+            shutter: Shutter {
+                open: self.shutter_open,
+                close: self.shutter_close,
+            },
         }
     }
 
@@ -0,0 +1,171 @@
+use raytracing_in_one_weekend::writing::ImageFormat;
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: raytracing-in-one-weekend [OPTIONS]
+
+Renders a scene and writes the image to a file or, if no output path is
+given, as PPM to stdout.
+
+Options:
+  -o, --output <PATH>     File to write the image to
+  -f, --format <FORMAT>   ppm, png, png-rgba, pfm or hdr
+                          [default: derived from the output path, else ppm]
+      --scene <PATH>      Scene file to render
+      --builtin <NAME>    Built-in scene to render [default: random-spheres]
+  -w, --width <PIXELS>    Image width, overriding the scene
+  -a, --aspect <RATIO>    Aspect ratio as a number or e.g. 16:9, overriding the scene
+  -s, --samples <N>       Samples per pixel, overriding the scene
+  -d, --max-depth <N>     Maximum number of ray bounces, overriding the scene
+  -t, --threads <N>       Number of render threads [default: available cores]
+      --seed <N>          Seed for the random number generator [default: random]
+  -h, --help              Print this help";
+
+pub enum SceneSource {
+    File(PathBuf),
+    Builtin(String),
+}
+
+pub struct Options {
+    pub output: Option<PathBuf>,
+    pub format: ImageFormat,
+    pub scene: SceneSource,
+    pub image_width: Option<u32>,
+    pub aspect_ratio: Option<f32>,
+    pub samples_per_pixel: Option<u32>,
+    pub max_ray_depth: Option<u32>,
+    pub threads: u32,
+    pub seed: Option<u64>,
+}
+
+pub enum Command {
+    Render(Options),
+    Help,
+}
+
+/// Parses the command line arguments, excluding the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+
+    let mut output = None;
+    let mut format = None;
+    let mut scene_file = None;
+    let mut builtin = None;
+    let mut image_width = None;
+    let mut aspect_ratio = None;
+    let mut samples_per_pixel = None;
+    let mut max_ray_depth = None;
+    let mut threads = None;
+    let mut seed = None;
+
+    while let Some(arg) = args.next() {
+        // Allow both "--option value" and "--option=value"
+        let (option, inline_value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => {
+                (option.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+
+        if option == "-h" || option == "--help" {
+            return Ok(Command::Help);
+        }
+
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("'{option}' requires a value"))
+        };
+
+        match option.as_str() {
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let name = value()?;
+                let parsed = ImageFormat::from_name(&name).ok_or_else(|| {
+                    format!(
+                        "unknown format '{name}', expected one of: {}",
+                        ImageFormat::NAMES.join(", ")
+                    )
+                })?;
+                format = Some(parsed);
+            }
+            "--scene" => scene_file = Some(PathBuf::from(value()?)),
+            "--builtin" => builtin = Some(value()?),
+            "-w" | "--width" => image_width = Some(positive(&option, &value()?)?),
+            "-a" | "--aspect" => aspect_ratio = Some(parse_aspect_ratio(&value()?)?),
+            "-s" | "--samples" => samples_per_pixel = Some(positive(&option, &value()?)?),
+            "-d" | "--max-depth" => max_ray_depth = Some(positive(&option, &value()?)?),
+            "-t" | "--threads" => threads = Some(positive(&option, &value()?)?),
+            "--seed" => {
+                let text = value()?;
+                let parsed = text
+                    .parse()
+                    .map_err(|_| format!("'{text}' is not a valid seed"))?;
+                seed = Some(parsed);
+            }
+            _ => return Err(format!("unknown argument '{option}'")),
+        }
+    }
+
+    let scene = match (scene_file, builtin) {
+        (Some(_), Some(_)) => return Err("'--scene' and '--builtin' can't be combined".into()),
+        (Some(path), None) => SceneSource::File(path),
+        (None, Some(name)) => SceneSource::Builtin(name),
+        (None, None) => SceneSource::Builtin("random-spheres".to_string()),
+    };
+
+    let format = match (format, &output) {
+        (Some(format), _) => format,
+        (None, Some(path)) => ImageFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "can't tell the format of '{}' from its extension, please pass '--format'",
+                path.display()
+            )
+        })?,
+        (None, None) => ImageFormat::Ppm,
+    };
+
+    let threads = match threads {
+        Some(threads) => threads,
+        None => std::thread::available_parallelism().map_or(1, |n| n.get() as u32),
+    };
+
+    Ok(Command::Render(Options {
+        output,
+        format,
+        scene,
+        image_width,
+        aspect_ratio,
+        samples_per_pixel,
+        max_ray_depth,
+        threads,
+        seed,
+    }))
+}
+
+fn positive(option: &str, text: &str) -> Result<u32, String> {
+    match text.parse() {
+        Ok(0) | Err(_) => Err(format!(
+            "'{option}' has to be a positive whole number, got '{text}'"
+        )),
+        Ok(number) => Ok(number),
+    }
+}
+
+/// Accepts either a plain number or a ratio written as "width:height".
+fn parse_aspect_ratio(text: &str) -> Result<f32, String> {
+    let ratio = match text.split_once(':') {
+        Some((width, height)) => match (width.parse::<f32>(), height.parse::<f32>()) {
+            (Ok(width), Ok(height)) => width / height,
+            _ => f32::NAN,
+        },
+        None => text.parse().unwrap_or(f32::NAN),
+    };
+
+    match ratio.is_finite() && ratio > 0.0 {
+        true => Ok(ratio),
+        false => Err(format!("'{text}' is not a valid aspect ratio")),
+    }
+}
@@ -21,6 +21,19 @@ impl Color {
     }
 
     pub fn pixel_format(self, samples: u32) -> String {
+        let [r, g, b] = self.to_rgb8(samples);
+        format!("{} {} {}\n", r, g, b)
+    }
+
+    /// Averages the accumulated samples without any gamma correction or
+    /// clamping, for high dynamic range output.
+    pub fn to_linear(self, samples: u32) -> Color {
+        self / samples as f32
+    }
+
+    /// Averages the accumulated samples, applies gamma correction and scales
+    /// the components to 0..=MAX_VALUE.
+    pub fn to_rgb8(self, samples: u32) -> [u8; 3] {
         let Color {
             mut r,
             mut g,
@@ -41,12 +54,20 @@ impl Color {
         // is exactly 1.0. To avoid writing values above MAX_VALUE, the
         // scaled up colors get clamped below.
         let upscale = (MAX_VALUE + 1) as f32;
-        format!(
-            "{} {} {}\n",
-            ((r * upscale) as u32).clamp(0, MAX_VALUE),
-            ((g * upscale) as u32).clamp(0, MAX_VALUE),
-            ((b * upscale) as u32).clamp(0, MAX_VALUE),
-        )
+        [
+            ((r * upscale) as u32).clamp(0, MAX_VALUE) as u8,
+            ((g * upscale) as u32).clamp(0, MAX_VALUE) as u8,
+            ((b * upscale) as u32).clamp(0, MAX_VALUE) as u8,
+        ]
+    }
+
+    /// Perceived brightness of a linear color, with the Rec. 709 weights.
+    pub fn luminance(self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn is_black(self) -> bool {
+        self.r <= 0.0 && self.g <= 0.0 && self.b <= 0.0
     }
 
     pub fn random() -> Self {
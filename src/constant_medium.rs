@@ -0,0 +1,69 @@
+use crate::aabb::Aabb;
+use crate::interval::{self, Interval};
+use crate::{random, HitRecord, Hittable, Ray, SharedHittable, SharedMaterial, Vec3};
+
+use std::sync::Arc;
+
+/// Volume of uniform density filling a boundary object, like smoke or fog.
+/// A ray passing through it scatters after an exponentially distributed
+/// distance, using the material as phase function, e.g. Isotropic.
+///
+/// The boundary has to be closed and convex, as only the first stretch of
+/// the ray inside of it is considered.
+pub struct ConstantMedium {
+    boundary: SharedHittable,
+    negative_inverse_density: f32,
+    phase_function: SharedMaterial,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: SharedHittable, density: f32, phase_function: SharedMaterial) -> Self {
+        Self {
+            boundary,
+            negative_inverse_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        // Find where the ray enters and leaves the boundary, also if it
+        // starts inside of it
+        let Some(entry) = self.boundary.hit(ray, interval::REAL_LINE) else {
+            return false;
+        };
+        let exit_t = Interval::new(entry.t + 0.0001, f32::INFINITY);
+        let Some(exit) = self.boundary.hit(ray, exit_t) else {
+            return false;
+        };
+
+        let start = entry.t.max(allowed_t.min).max(0.0);
+        let end = exit.t.min(allowed_t.max);
+        if start >= end {
+            return false;
+        }
+
+        let ray_length = ray.direction().length();
+        let distance_inside = (end - start) * ray_length;
+        let hit_distance = self.negative_inverse_density * random::random().ln();
+        if hit_distance > distance_inside {
+            return false;
+        }
+
+        hit_rec.t = start + hit_distance / ray_length;
+        hit_rec.point = ray.at(hit_rec.t);
+        // Neither matter for the phase function
+        hit_rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        hit_rec.front_face = true;
+        hit_rec.u = 0.0;
+        hit_rec.v = 0.0;
+        hit_rec.material = Some(Arc::clone(&self.phase_function));
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
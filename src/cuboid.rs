@@ -0,0 +1,50 @@
+use crate::aabb::Aabb;
+use crate::hittable_list::HittableList;
+use crate::quad::Quad;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedMaterial, Vec3};
+
+use std::sync::Arc;
+
+/// Axis-aligned box made of six quads facing outwards.
+pub struct Cuboid {
+    sides: HittableList,
+}
+
+impl Cuboid {
+    /// Creates the box spanned by two opposite corners, in any order.
+    pub fn new(a: Point3, b: Point3, material: SharedMaterial) -> Self {
+        let bounds = Aabb::from_points(a, b);
+        let min = Point3::new(bounds.x.min, bounds.y.min, bounds.z.min);
+        let max = Point3::new(bounds.x.max, bounds.y.max, bounds.z.max);
+
+        let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+        let sides = [
+            (Point3::new(min.x, min.y, max.z), dx, dy),  // front
+            (Point3::new(max.x, min.y, max.z), -dz, dy), // right
+            (Point3::new(max.x, min.y, min.z), -dx, dy), // back
+            (Point3::new(min.x, min.y, min.z), dz, dy),  // left
+            (Point3::new(min.x, max.y, max.z), dx, -dz), // top
+            (Point3::new(min.x, min.y, min.z), dx, dz),  // bottom
+        ];
+
+        let mut list = HittableList::default();
+        for (corner, u, v) in sides {
+            list.add(Arc::new(Quad::new(corner, u, v, Arc::clone(&material))));
+        }
+
+        Self { sides: list }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        self.sides.hit_mutating(ray, allowed_t, hit_rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sides.bounding_box()
+    }
+}
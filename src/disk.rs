@@ -0,0 +1,91 @@
+use crate::aabb::Aabb;
+use crate::interval::Interval;
+use crate::onb::Onb;
+use crate::plane;
+use crate::random;
+use crate::triangle::BOUNDING_BOX_PADDING;
+use crate::{HitRecord, Hittable, Point3, Ray, SharedMaterial, Vec3};
+
+use std::f32::consts::PI;
+
+/// Flat circle facing the direction of its normal. The texture coordinates
+/// map the square around the disk onto the unit square.
+pub struct Disk {
+    center: Point3,
+    radius: f32,
+    basis: Onb,
+    material: SharedMaterial,
+    bounding_box: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f32, material: SharedMaterial) -> Self {
+        let basis = Onb::from_w(normal);
+
+        // Along each axis, the disk reaches as far as the sine of the angle
+        // between the axis and the normal allows
+        let w = basis.w;
+        let extent = |normal: f32, coordinate: f32| {
+            let reach = radius * (1.0 - normal * normal).max(0.0).sqrt();
+            Interval::new(coordinate - reach, coordinate + reach)
+        };
+        let bounding_box = Aabb::new(
+            extent(w.x, center.x),
+            extent(w.y, center.y),
+            extent(w.z, center.z),
+        )
+        .padded(BOUNDING_BOX_PADDING);
+
+        Self {
+            center,
+            radius,
+            basis,
+            material,
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let Some((t, point)) = plane::intersect(ray, self.center, self.basis.w, allowed_t) else {
+            return false;
+        };
+
+        let offset = point - self.center;
+        if offset.length_squared() > self.radius * self.radius {
+            return false;
+        }
+
+        let scale = 0.5 / self.radius;
+        hit_rec.u = 0.5 + scale * Vec3::dot(offset, self.basis.u);
+        hit_rec.v = 0.5 + scale * Vec3::dot(offset, self.basis.v);
+        plane::fill_hit_record(hit_rec, ray, t, point, self.basis.w, &self.material);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let ray = Ray::new(origin, direction);
+        let area = PI * self.radius * self.radius;
+        match self.hit(&ray, Interval::new(0.001, f32::INFINITY)) {
+            Some(hit) => plane::solid_angle_pdf(direction, hit.t, self.basis.w, area),
+            None => 0.0,
+        }
+    }
+
+    fn random_direction(&self, origin: Point3) -> Vec3 {
+        // The square root spreads the points evenly over the area
+        let radius = self.radius * random::random().sqrt();
+        let phi = 2.0 * PI * random::random();
+        let point = self.center
+            + self
+                .basis
+                .local(radius * phi.cos(), radius * phi.sin(), 0.0);
+        point - origin
+    }
+}
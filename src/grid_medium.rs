@@ -0,0 +1,180 @@
+use crate::aabb::Aabb;
+use crate::material::{Material, Scatter};
+use crate::voxel_grid::VoxelGrid;
+use crate::{random, Color, HitRecord, Hittable, Interval, Point3, Ray, SharedMaterial, Vec3};
+
+use std::sync::Arc;
+
+/// Volume whose density varies according to a voxel grid stretched over a
+/// box, like a cloud or smoke from a simulation. Collisions are found with
+/// delta tracking, against the grid's maximum density as majorant. Shadow
+/// rays pass through and get attenuated by the transmittance instead, which
+/// ratio tracking estimates without the noise of a blocked or not result.
+pub struct GridMedium {
+    grid: Arc<VoxelGrid>,
+    bounds: Aabb,
+    density_scale: f32,
+    material: SharedMaterial,
+}
+
+impl GridMedium {
+    /// Spans the grid over the box between the corners 'min' and 'max'. The
+    /// grid's densities get multiplied by 'density_scale' and light scatters
+    /// with the phase function, e.g. HenyeyGreenstein.
+    pub fn new(
+        grid: Arc<VoxelGrid>,
+        min: Point3,
+        max: Point3,
+        density_scale: f32,
+        phase_function: SharedMaterial,
+    ) -> Self {
+        Self {
+            grid,
+            bounds: Aabb::from_points(min, max),
+            density_scale,
+            material: phase_function,
+        }
+    }
+
+    /// Makes the volume glow like fire, with 'emission' at the densest
+    /// voxels and proportionally less elsewhere.
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        let emissive = EmissiveVolume {
+            phase_function: self.material,
+            grid: Arc::clone(&self.grid),
+            bounds: self.bounds,
+            emission,
+        };
+        self.material = Arc::new(emissive);
+        self
+    }
+
+    pub fn density(&self, point: Point3) -> f32 {
+        self.density_scale * self.grid.density(grid_coordinates(&self.bounds, point))
+    }
+
+    fn majorant(&self) -> f32 {
+        self.density_scale * self.grid.max_density()
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let majorant = self.majorant();
+        if majorant <= 0.0 {
+            return false;
+        }
+        let Some(inside) = self.bounds.clip(ray, allowed_t) else {
+            return false;
+        };
+        let ray_length = ray.direction().length();
+
+        // Delta tracking: step with the majorant and accept a collision with
+        // the probability of the actual density, the rejected ones being
+        // collisions with fictitious particles
+        let mut t = inside.min;
+        loop {
+            t += free_path(majorant) / ray_length;
+            if t >= inside.max {
+                return false;
+            }
+            if random::random() * majorant < self.density(ray.at(t)) {
+                break;
+            }
+        }
+
+        hit_rec.t = t;
+        hit_rec.point = ray.at(t);
+        // Neither matter for the phase function
+        hit_rec.normal = Vec3::new(1.0, 0.0, 0.0);
+        hit_rec.front_face = true;
+        hit_rec.u = 0.0;
+        hit_rec.v = 0.0;
+        hit_rec.material = Some(Arc::clone(&self.material));
+
+        true
+    }
+
+    fn shadow_hit_mutating(
+        &self,
+        _ray: &Ray,
+        _allowed_t: Interval,
+        _hit_rec: &mut HitRecord,
+    ) -> bool {
+        false
+    }
+
+    /// Ratio tracking: the same steps as delta tracking, but instead of
+    /// stopping at a collision, every step keeps the probability that it
+    /// was a fictitious one.
+    fn transmittance(&self, ray: &Ray, allowed_t: Interval) -> f32 {
+        let majorant = self.majorant();
+        if majorant <= 0.0 {
+            return 1.0;
+        }
+        let Some(inside) = self.bounds.clip(ray, allowed_t) else {
+            return 1.0;
+        };
+        let ray_length = ray.direction().length();
+
+        let mut transmittance = 1.0;
+        let mut t = inside.min;
+        loop {
+            t += free_path(majorant) / ray_length;
+            if t >= inside.max {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density(ray.at(t)) / majorant;
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+/// Exponentially distributed distance to the next collision.
+fn free_path(density: f32) -> f32 {
+    -(1.0 - random::random()).ln() / density
+}
+
+/// Position of a point relative to the box, with the box being the unit cube.
+fn grid_coordinates(bounds: &Aabb, point: Point3) -> Point3 {
+    let relative = |axis: Interval, coordinate: f32| (coordinate - axis.min) / axis.size();
+    Point3::new(
+        relative(bounds.x, point.x),
+        relative(bounds.y, point.y),
+        relative(bounds.z, point.z),
+    )
+}
+
+/// Phase function of a glowing volume, emitting light in proportion to the
+/// density relative to the grid's maximum.
+struct EmissiveVolume {
+    phase_function: SharedMaterial,
+    grid: Arc<VoxelGrid>,
+    bounds: Aabb,
+    emission: Color,
+}
+
+impl Material for EmissiveVolume {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
+        self.phase_function.scatter(ray, hit_record)
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        self.phase_function
+            .scattering_pdf(ray, hit_record, direction)
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        self.phase_function.evaluate(ray, hit_record, direction)
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        let density = self
+            .grid
+            .density(grid_coordinates(&self.bounds, hit_record.point));
+        (density / self.grid.max_density()) * self.emission
+    }
+}
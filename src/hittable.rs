@@ -1,3 +1,4 @@
+use crate::aabb::Aabb;
 use crate::{Interval, Point3, Ray, SharedMaterial, Vec3};
 
 #[derive(Default)]
@@ -6,6 +7,9 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub material: Option<SharedMaterial>,
     pub t: f32,
+    /// Surface coordinates of the hit point
+    pub u: f32,
+    pub v: f32,
     pub front_face: bool,
 }
 
@@ -16,6 +20,8 @@ impl HitRecord {
             normal,
             material: Some(material),
             t,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
         }
     }
@@ -41,4 +47,40 @@ pub trait Hittable {
             false => None,
         }
     }
+
+    /// Like 'hit_mutating', but for shadow rays, which pass through volumes
+    /// that account for the light they let through with 'transmittance'.
+    fn shadow_hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        self.hit_mutating(ray, allowed_t, hit_rec)
+    }
+
+    fn shadow_hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut hit_record = HitRecord::default();
+        match self.shadow_hit_mutating(ray, allowed_t, &mut hit_record) {
+            true => Some(hit_record),
+            false => None,
+        }
+    }
+
+    /// Fraction of light that makes it along the ray between the given t
+    /// through the volumes that shadow rays pass through. Everything else
+    /// lets all light through.
+    fn transmittance(&self, _ray: &Ray, _allowed_t: Interval) -> f32 {
+        1.0
+    }
+
+    /// Box enclosing the whole object, used by the bounding volume hierarchy.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Density, per unit solid angle, with which 'random_direction' picks
+    /// 'direction' from 'origin'. Only objects that can be sampled as
+    /// lights override this, everything else can't be sampled and returns 0.
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Random direction from 'origin' towards a point on the object.
+    fn random_direction(&self, _origin: Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
@@ -1,31 +1,55 @@
-use crate::{HitRecord, Hittable, Interval, SharedHittable};
-
-use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::random;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedHittable, Vec3};
 
 #[derive(Default, Clone)]
 pub struct HittableList {
-    objects: Vec<Arc<dyn Hittable + Send + Sync>>,
+    objects: Vec<SharedHittable>,
+    bounding_box: Aabb,
 }
 
 impl HittableList {
     pub fn new(objects: Vec<SharedHittable>) -> Self {
-        Self { objects }
+        let mut list = Self::default();
+        for object in objects {
+            list.add(object);
+        }
+        list
     }
 
     pub fn add(&mut self, object: SharedHittable) {
+        self.bounding_box = Aabb::enclosing(self.bounding_box, object.bounding_box());
         self.objects.push(object);
     }
+
+    pub fn objects(&self) -> &[SharedHittable] {
+        &self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
 }
 
-impl Hittable for HittableList {
-    fn hit_mutating(&self, ray: &crate::Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+impl HittableList {
+    /// Finds the closest of the hits that 'hit' reports for the objects.
+    fn closest_hit(
+        &self,
+        allowed_t: Interval,
+        hit_rec: &mut HitRecord,
+        hit: impl Fn(&SharedHittable, Interval, &mut HitRecord) -> bool,
+    ) -> bool {
         let mut current_rec = HitRecord::default();
         let mut hit_anything = false;
         let mut closest_t = allowed_t.max;
 
         for object in &self.objects {
-            if object.hit_mutating(
-                ray,
+            if hit(
+                object,
                 Interval::new(allowed_t.min, closest_t),
                 &mut current_rec,
             ) {
@@ -41,3 +65,53 @@ impl Hittable for HittableList {
         hit_anything
     }
 }
+
+impl Hittable for HittableList {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        self.closest_hit(allowed_t, hit_rec, |object, allowed_t, hit_rec| {
+            object.hit_mutating(ray, allowed_t, hit_rec)
+        })
+    }
+
+    fn shadow_hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        self.closest_hit(allowed_t, hit_rec, |object, allowed_t, hit_rec| {
+            object.shadow_hit_mutating(ray, allowed_t, hit_rec)
+        })
+    }
+
+    fn transmittance(&self, ray: &Ray, allowed_t: Interval) -> f32 {
+        self.objects
+            .iter()
+            .map(|object| object.transmittance(ray, allowed_t))
+            .product()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    /// The objects are picked with equal probability, so the density is the
+    /// average of theirs.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f32 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        sum / self.objects.len() as f32
+    }
+
+    fn random_direction(&self, origin: Point3) -> Vec3 {
+        if self.objects.is_empty() {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+
+        let count = self.objects.len();
+        let index = ((random::random() * count as f32) as usize).min(count - 1);
+        self.objects[index].random_direction(origin)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::aabb::Aabb;
+use crate::transform::Transform;
+use crate::{HitRecord, Hittable, Interval, Ray, SharedHittable};
+
+/// Places an object in the scene with a transform, so one object can be
+/// shared between several instances in different poses.
+pub struct Instance {
+    object: SharedHittable,
+    transform: Transform,
+    bounding_box: Aabb,
+}
+
+impl Instance {
+    pub fn new(object: SharedHittable, transform: Transform) -> Self {
+        let bounding_box = transform.bounding_box(object.bounding_box());
+        Self {
+            object,
+            transform,
+            bounding_box,
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl Instance {
+    /// The ray in the object's space. The direction isn't normalized, so t
+    /// is the same in both spaces.
+    fn object_ray(&self, ray: &Ray) -> Ray {
+        let to_object = self.transform.inverse();
+        Ray::with_time(
+            to_object.point(ray.origin()),
+            to_object.vector(ray.direction()),
+            ray.time(),
+        )
+    }
+
+    /// Moves a hit found with the object ray back into world space.
+    fn to_world(&self, ray: &Ray, hit_rec: &mut HitRecord) {
+        // The normal already faces against the ray, which the transform
+        // keeps, so front_face stays valid
+        hit_rec.point = ray.at(hit_rec.t);
+        hit_rec.normal = self.transform.normal(hit_rec.normal).normalized();
+    }
+}
+
+impl Hittable for Instance {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let object_ray = self.object_ray(ray);
+        if !self.object.hit_mutating(&object_ray, allowed_t, hit_rec) {
+            return false;
+        }
+        self.to_world(ray, hit_rec);
+        true
+    }
+
+    fn shadow_hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let object_ray = self.object_ray(ray);
+        if !self
+            .object
+            .shadow_hit_mutating(&object_ray, allowed_t, hit_rec)
+        {
+            return false;
+        }
+        self.to_world(ray, hit_rec);
+        true
+    }
+
+    fn transmittance(&self, ray: &Ray, allowed_t: Interval) -> f32 {
+        self.object.transmittance(&self.object_ray(ray), allowed_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
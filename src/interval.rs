@@ -1,7 +1,7 @@
 pub const EMPTY: Interval = Interval::new(f32::INFINITY, f32::NEG_INFINITY);
 pub const REAL_LINE: Interval = Interval::new(f32::NEG_INFINITY, f32::INFINITY);
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct Interval {
     pub min: f32,
     pub max: f32,
@@ -12,6 +12,19 @@ impl Interval {
         Self { min, max }
     }
 
+    /// The smallest interval containing both given intervals.
+    pub fn enclosing(a: Interval, b: Interval) -> Self {
+        Self::new(a.min.min(b.min), a.max.max(b.max))
+    }
+
+    pub fn size(self) -> f32 {
+        self.max - self.min
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.min > self.max
+    }
+
     pub fn contains(self, x: f32) -> bool {
         self.min <= x && x <= self.max
     }
@@ -19,4 +32,10 @@ impl Interval {
     pub fn surrounds(self, x: f32) -> bool {
         self.min < x && x < self.max
     }
+
+    /// Pads the interval by 'delta' in total, half on each side.
+    pub fn expanded(self, delta: f32) -> Self {
+        let padding = delta / 2.0;
+        Self::new(self.min - padding, self.max + padding)
+    }
 }
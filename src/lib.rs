@@ -1,27 +1,55 @@
+pub mod aabb;
+pub mod background;
+pub mod bvh;
 pub mod camera;
 pub mod color;
+pub mod constant_medium;
+pub mod cuboid;
+pub mod disk;
+pub mod grid_medium;
 pub mod hittable;
 pub mod hittable_list;
+pub mod instance;
 pub mod interval;
 pub mod material;
+pub mod moving_sphere;
+pub mod obj;
+pub mod onb;
+pub mod perlin;
+pub mod plane;
 pub mod point3;
+pub mod quad;
 pub mod random;
 pub mod ray;
+pub mod reading;
 pub mod renderer;
+pub mod scene;
 pub mod sphere;
+pub mod texture;
+pub mod transform;
+pub mod triangle;
+pub mod triangle_mesh;
 pub mod vec3;
+pub mod voxel_grid;
 pub mod writing;
 
 use std::sync::Arc;
 
+pub use background::Background;
 pub use color::Color;
 pub use hittable::{HitRecord, Hittable};
 pub use interval::Interval;
-pub use material::{Dialectric, Lambertian, Material, Metal, Scatter};
+pub use material::{
+    Conductor, Dialectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Lobe, Material,
+    Metal, Principled, RoughConductor, RoughDielectric, Scatter,
+};
 pub use point3::Point3;
 pub use ray::Ray;
 pub use renderer::Renderer;
+pub use texture::Texture;
 pub use vec3::Vec3;
 
 pub type SharedMaterial = Arc<dyn Material + Send + Sync>;
-type SharedHittable = Arc<dyn Hittable + Send + Sync>;
+pub type SharedHittable = Arc<dyn Hittable + Send + Sync>;
+pub type SharedTexture = Arc<dyn Texture + Send + Sync>;
+pub type SharedBackground = Arc<dyn Background + Send + Sync>;
@@ -1,26 +1,89 @@
+mod cli;
+
+use cli::{Command, SceneSource};
+
+use raytracing_in_one_weekend::background::{Gradient, Solid};
 use raytracing_in_one_weekend::camera::CameraBuilder;
+use raytracing_in_one_weekend::cuboid::Cuboid;
 use raytracing_in_one_weekend::hittable_list::HittableList;
+use raytracing_in_one_weekend::instance::Instance;
+use raytracing_in_one_weekend::quad::Quad;
+use raytracing_in_one_weekend::scene::Scene;
 use raytracing_in_one_weekend::sphere::Sphere;
+use raytracing_in_one_weekend::transform::Transform;
 use raytracing_in_one_weekend::{
-    random, Color, Dialectric, Lambertian, Metal, Point3, Renderer, SharedMaterial, Vec3,
+    random, Color, Dialectric, DiffuseLight, Lambertian, Metal, Point3, SharedHittable,
+    SharedMaterial, Vec3,
 };
 
 use std::sync::Arc;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    let num_threads: u32 = match args.get(1) {
-        Some(argument) => argument
-            .parse()
-            .map_err(|_| "Please provide a valid argument for the number of threads")?,
-        None => 1,
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("Error: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match cli::parse(std::env::args().skip(1))
+        .map_err(|error| format!("{error}\nRun with '--help' to see the available options"))?
+    {
+        Command::Render(options) => options,
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+    };
+
+    // The seed has to be set before building the scene, as the built-in
+    // scenes are randomized
+    let seed = options.seed.unwrap_or_else(rand::random);
+    random::set_seed(seed);
+    eprintln!("Seed: {seed}");
+
+    let mut scene = match &options.scene {
+        SceneSource::File(path) => Scene::load(path)?,
+        SceneSource::Builtin(name) => builtin_scene(name).ok_or_else(|| {
+            format!(
+                "unknown built-in scene '{name}', expected one of: {}",
+                BUILTIN_SCENES.join(", ")
+            )
+        })?,
     };
 
-    random::initialize();
+    if let Some(image_width) = options.image_width {
+        scene.camera.image_width = image_width;
+    }
+    if let Some(aspect_ratio) = options.aspect_ratio {
+        scene.camera.aspect_ratio = aspect_ratio;
+    }
+    if let Some(samples_per_pixel) = options.samples_per_pixel {
+        scene.samples_per_pixel = samples_per_pixel;
+    }
+    if let Some(max_ray_depth) = options.max_ray_depth {
+        scene.max_ray_depth = max_ray_depth;
+    }
+
+    let renderer = scene.into_renderer();
+    renderer.start(options.threads, options.format, options.output.as_deref())?;
+
+    Ok(())
+}
+
+const BUILTIN_SCENES: [&str; 3] = ["random-spheres", "cornell-spheres", "cornell-box"];
 
-    let world = build_scene();
+fn builtin_scene(name: &str) -> Option<Scene> {
+    match name {
+        "random-spheres" => Some(random_spheres()),
+        "cornell-spheres" => Some(cornell_spheres()),
+        "cornell-box" => Some(cornell_box()),
+        _ => None,
+    }
+}
 
-    let camera_builder = CameraBuilder {
+fn random_spheres() -> Scene {
+    let camera = CameraBuilder {
         image_width: 100,
         aspect_ratio: 16.0 / 9.0,
         vertical_fov: 20.0,
@@ -29,17 +92,159 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         view_up: Vec3::new(0.0, 1.0, 0.0),
         focus_distance: 10.0,
         defocus_angle: 0.6,
+        ..Default::default()
     };
 
-    let camera = camera_builder.finalize();
+    Scene {
+        world: build_scene(),
+        lights: HittableList::default(),
+        camera,
+        samples_per_pixel: 500,
+        max_ray_depth: 20,
+        background: Arc::new(Gradient),
+    }
+}
 
-    let samples_per_pixel = 500;
-    let max_ray_depth = 20;
-    let renderer = Renderer::new(world, camera, samples_per_pixel, max_ray_depth);
+/// Cornell box in the style of smallpt, with large spheres as walls, lit only
+/// by a light sphere sticking out of the ceiling.
+fn cornell_spheres() -> Scene {
+    let mut world = HittableList::default();
 
-    renderer.start(num_threads)?;
+    let red = Arc::new(Lambertian::new(Color::new(0.75, 0.25, 0.25)));
+    let blue = Arc::new(Lambertian::new(Color::new(0.25, 0.25, 0.75)));
+    let white = Arc::new(Lambertian::new(Color::new(0.75, 0.75, 0.75)));
+    let mirror = Arc::new(Metal::new(Color::new(0.999, 0.999, 0.999), 0.0));
+    let glass = Arc::new(Dialectric::new(1.5));
+    let light = Arc::new(DiffuseLight::new(Color::new(50.0, 50.0, 50.0)));
 
-    Ok(())
+    let wall_radius = 1e3;
+    let walls: [(Point3, SharedMaterial); 5] = [
+        (Point3::new(1.0 + wall_radius, 40.8, 81.6), red), // left
+        (Point3::new(99.0 - wall_radius, 40.8, 81.6), blue), // right
+        (Point3::new(50.0, 40.8, -wall_radius), white.clone()), // back
+        (Point3::new(50.0, -wall_radius, 81.6), white.clone()), // floor
+        (Point3::new(50.0, 81.6 + wall_radius, 81.6), white), // ceiling
+    ];
+    for (center, material) in walls {
+        world.add(Arc::new(Sphere::new(center, wall_radius, material)));
+    }
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(27.0, 16.5, 47.0),
+        16.5,
+        mirror,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(73.0, 16.5, 78.0),
+        16.5,
+        glass,
+    )));
+    let light: SharedHittable = Arc::new(Sphere::new(Point3::new(50.0, 90.0, 81.6), 10.0, light));
+    world.add(Arc::clone(&light));
+
+    let camera = CameraBuilder {
+        image_width: 400,
+        aspect_ratio: 1.0,
+        vertical_fov: 40.0,
+        position: Point3::new(50.0, 52.0, 295.6),
+        look_at: Point3::new(50.0, 47.7, 195.6),
+        ..Default::default()
+    };
+
+    Scene {
+        world,
+        lights: HittableList::new(vec![light]),
+        camera,
+        samples_per_pixel: 200,
+        max_ray_depth: 50,
+        background: Arc::new(Solid::new(Color::new(0.0, 0.0, 0.0))),
+    }
+}
+
+/// The classic Cornell box with two rotated boxes, built from quads.
+fn cornell_box() -> Scene {
+    let mut world = HittableList::default();
+
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0)));
+
+    let quads: [(Point3, Vec3, Vec3, SharedMaterial); 5] = [
+        (
+            Point3::new(555.0, 0.0, 0.0),
+            Vec3::new(0.0, 555.0, 0.0),
+            Vec3::new(0.0, 0.0, 555.0),
+            green,
+        ),
+        (
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 555.0, 0.0),
+            Vec3::new(0.0, 0.0, 555.0),
+            red,
+        ),
+        (
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(555.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 555.0),
+            white.clone(),
+        ),
+        (
+            Point3::new(555.0, 555.0, 555.0),
+            Vec3::new(-555.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -555.0),
+            white.clone(),
+        ),
+        (
+            Point3::new(0.0, 0.0, 555.0),
+            Vec3::new(555.0, 0.0, 0.0),
+            Vec3::new(0.0, 555.0, 0.0),
+            white.clone(),
+        ),
+    ];
+    for (corner, u, v, material) in quads {
+        world.add(Arc::new(Quad::new(corner, u, v, material)));
+    }
+
+    let light: SharedHittable = Arc::new(Quad::new(
+        Point3::new(343.0, 554.0, 332.0),
+        Vec3::new(-130.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -105.0),
+        light,
+    ));
+    world.add(Arc::clone(&light));
+
+    let boxes = [
+        (165.0, 330.0, 15.0, Vec3::new(265.0, 0.0, 295.0)),
+        (165.0, 165.0, -18.0, Vec3::new(130.0, 0.0, 65.0)),
+    ];
+    for (width, height, degrees, offset) in boxes {
+        let cuboid = Cuboid::new(
+            Point3::zero(),
+            Point3::new(width, height, width),
+            white.clone(),
+        );
+        let transform = Transform::rotation_y(degrees).then(Transform::translation(offset));
+        world.add(Arc::new(Instance::new(Arc::new(cuboid), transform)));
+    }
+
+    let camera = CameraBuilder {
+        image_width: 400,
+        aspect_ratio: 1.0,
+        vertical_fov: 40.0,
+        position: Point3::new(278.0, 278.0, -800.0),
+        look_at: Point3::new(278.0, 278.0, 0.0),
+        ..Default::default()
+    };
+
+    Scene {
+        world,
+        lights: HittableList::new(vec![light]),
+        camera,
+        samples_per_pixel: 200,
+        max_ray_depth: 50,
+        background: Arc::new(Solid::new(Color::new(0.0, 0.0, 0.0))),
+    }
 }
 
 fn build_scene() -> HittableList {
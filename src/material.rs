@@ -1,24 +1,87 @@
 mod dialectric;
+mod diffuse_light;
+mod henyey_greenstein;
+mod isotropic;
 mod lambertian;
 mod metal;
+mod microfacet;
+mod principled;
+mod rough_conductor;
+mod rough_dielectric;
 
 pub use dialectric::Dialectric;
+pub use diffuse_light::DiffuseLight;
+pub use henyey_greenstein::HenyeyGreenstein;
+pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
+pub use principled::Principled;
+pub use rough_conductor::{Conductor, RoughConductor};
+pub use rough_dielectric::RoughDielectric;
 
-use crate::{Color, HitRecord, Ray};
+use crate::{Color, HitRecord, Ray, Vec3};
+
+/// How the scattered direction was picked.
+#[derive(Debug, Clone, Copy)]
+pub enum Lobe {
+    /// A single direction, like the reflection of a mirror or the
+    /// refraction of glass. No other direction, and in particular none
+    /// towards a light, gets any of the light.
+    Specular,
+    /// Any of a range of directions, picked with density 'pdf' per unit
+    /// solid angle. The material can evaluate how much light it scatters
+    /// into other directions, so lights can be sampled directly.
+    Diffuse { pdf: f32 },
+}
 
 pub struct Scatter {
     pub ray: Ray,
+    /// Factor for the light arriving along 'ray'. For diffuse lobes, this is
+    /// the material's 'evaluate' for the direction divided by its pdf.
     pub attenuation: Color,
+    pub lobe: Lobe,
 }
 
 impl Scatter {
-    pub fn new(ray: Ray, attenuation: Color) -> Self {
-        Scatter { ray, attenuation }
+    pub fn specular(ray: Ray, attenuation: Color) -> Self {
+        Self {
+            ray,
+            attenuation,
+            lobe: Lobe::Specular,
+        }
+    }
+
+    pub fn diffuse(ray: Ray, attenuation: Color, pdf: f32) -> Self {
+        Self {
+            ray,
+            attenuation,
+            lobe: Lobe::Diffuse { pdf },
+        }
     }
 }
 
 pub trait Material {
+    /// Picks a direction to scatter the incoming 'ray' into, or None if the
+    /// ray gets absorbed.
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter>;
+
+    /// Density, per unit solid angle, with which 'scatter' picks
+    /// 'direction' from its diffuse lobe. Materials that only scatter
+    /// specularly return 0.
+    fn scattering_pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Fraction of the light arriving from 'direction' that gets scattered
+    /// back along 'ray', including the cosine towards the normal for
+    /// surfaces. Specular lobes don't contribute, they can't be hit by any
+    /// given direction.
+    fn evaluate(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Light given off by the surface itself, none by default.
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
 }
@@ -4,15 +4,49 @@ use crate::{random, Color, HitRecord, Ray, Vec3};
 
 pub struct Dialectric {
     refraction: f32,
+    /// Absorption coefficient per unit of distance inside, for each color
+    /// channel
+    absorption: Color,
 }
 
 impl Dialectric {
     pub fn new(index_of_refraction: f32) -> Self {
         Self {
             refraction: index_of_refraction,
+            absorption: Color::new(0.0, 0.0, 0.0),
         }
     }
 
+    /// Tints the glass by absorbing light along the way through it,
+    /// following the Beer–Lambert law. Light travelling a distance d keeps
+    /// exp(-absorption * d) of its strength, so thick glass gets darker and
+    /// more saturated than thin glass. The surfaces have to enclose the
+    /// inside for it to work. The components can't be negative, which would
+    /// make the glass add light.
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        assert!(
+            absorption.r >= 0.0 && absorption.g >= 0.0 && absorption.b >= 0.0,
+            "absorption can't be negative"
+        );
+        self.absorption = absorption;
+        self
+    }
+
+    /// Light left after travelling from the ray's origin to the hit, which
+    /// went through the glass if it hit the surface from the inside.
+    fn transmittance(&self, ray: &Ray, hit_record: &HitRecord) -> Color {
+        if hit_record.front_face || self.absorption.is_black() {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+
+        let distance = hit_record.t * ray.direction().length();
+        Color::new(
+            (-self.absorption.r * distance).exp(),
+            (-self.absorption.g * distance).exp(),
+            (-self.absorption.b * distance).exp(),
+        )
+    }
+
     fn reflectance(&self, cosine: f32, ref_idx: f32) -> f32 {
         let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
         r0 = r0 * r0;
@@ -39,9 +73,9 @@ impl Material for Dialectric {
             false => normalized_direction.refracted(hit_record.normal, refraction_ratio),
         };
 
-        let scattered_ray = Ray::new(hit_record.point, direction);
-        let attenuation = Color::new(1.0, 1.0, 1.0);
+        let scattered_ray = Ray::with_time(hit_record.point, direction, ray.time());
+        let attenuation = self.transmittance(ray, hit_record);
 
-        Some(Scatter::new(scattered_ray, attenuation))
+        Some(Scatter::specular(scattered_ray, attenuation))
     }
 }
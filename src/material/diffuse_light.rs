@@ -0,0 +1,24 @@
+use super::{Material, Scatter};
+
+use crate::{Color, HitRecord, Ray};
+
+/// Emits light equally in all directions and doesn't scatter any.
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _hit_record: &HitRecord) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        self.emit
+    }
+}
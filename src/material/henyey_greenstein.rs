@@ -0,0 +1,84 @@
+use super::{Material, Scatter};
+
+use crate::onb::Onb;
+use crate::texture::SolidColor;
+use crate::{random, Color, HitRecord, Ray, SharedTexture, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Henyey–Greenstein phase function, for volumes that scatter light
+/// preferably forwards (g > 0) or backwards (g < 0). With g = 0 it's the
+/// same as Isotropic.
+pub struct HenyeyGreenstein {
+    albedo: SharedTexture,
+    g: f32,
+}
+
+impl HenyeyGreenstein {
+    /// 'g' is the mean cosine of the scattering angle, in -1..1.
+    pub fn new(albedo: Color, g: f32) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)), g)
+    }
+
+    pub fn textured(albedo: SharedTexture, g: f32) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+
+    /// Density per unit solid angle of scattering by the angle with the
+    /// given cosine, which is also the density 'scatter' samples with.
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.g;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    /// Samples the cosine of the angle between the incoming and the
+    /// scattered direction by inverting the phase function's CDF.
+    fn sample_cos_theta(&self) -> f32 {
+        let g = self.g;
+        let xi = random::random();
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+
+        let term = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - term * term) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
+        let cos_theta = self.sample_cos_theta();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random::random();
+
+        let basis = Onb::from_w(ray.direction());
+        let direction = basis.local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+
+        let scattered = Ray::with_time(hit_record.point, direction, ray.time());
+        let attenuation = self
+            .albedo
+            .value(hit_record.u, hit_record.v, hit_record.point);
+        Some(Scatter::diffuse(
+            scattered,
+            attenuation,
+            self.phase(cos_theta),
+        ))
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, _hit_record: &HitRecord, direction: Vec3) -> f32 {
+        let cos_theta = Vec3::dot(ray.direction().normalized(), direction.normalized());
+        self.phase(cos_theta)
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        let albedo = self
+            .albedo
+            .value(hit_record.u, hit_record.v, hit_record.point);
+        albedo * self.scattering_pdf(ray, hit_record, direction)
+    }
+}
@@ -0,0 +1,44 @@
+use super::{Material, Scatter};
+
+use crate::texture::SolidColor;
+use crate::{Color, HitRecord, Ray, SharedTexture, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Phase function of a volume that scatters light equally in all
+/// directions.
+pub struct Isotropic {
+    albedo: SharedTexture,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: SharedTexture) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
+        let scattered = Ray::with_time(hit_record.point, Vec3::random_unit_vector(), ray.time());
+        let attenuation = self
+            .albedo
+            .value(hit_record.u, hit_record.v, hit_record.point);
+        Some(Scatter::diffuse(scattered, attenuation, 1.0 / (4.0 * PI)))
+    }
+
+    fn scattering_pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        let albedo = self
+            .albedo
+            .value(hit_record.u, hit_record.v, hit_record.point);
+        albedo * self.scattering_pdf(ray, hit_record, direction)
+    }
+}
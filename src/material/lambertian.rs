@@ -1,29 +1,47 @@
 use super::{Material, Scatter};
 
-use crate::{Color, HitRecord, Ray, Vec3};
+use crate::onb::Onb;
+use crate::texture::SolidColor;
+use crate::{Color, HitRecord, Ray, SharedTexture, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
 
 pub struct Lambertian {
-    albedo: Color,
+    albedo: SharedTexture,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: SharedTexture) -> Self {
         Lambertian { albedo }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, hit_rec: &HitRecord) -> Option<Scatter> {
-        let mut scatter_direction = hit_rec.normal + Vec3::random_unit_vector();
-
-        // Catch degenerate scatter direction
-        if scatter_direction.near_zero() {
-            scatter_direction = hit_rec.normal;
-        }
+    fn scatter(&self, ray: &Ray, hit_rec: &HitRecord) -> Option<Scatter> {
+        let local = Vec3::random_cosine_direction();
+        let scatter_direction = Onb::from_w(hit_rec.normal).local(local.x, local.y, local.z);
 
-        let scattered = Ray::new(hit_rec.point, scatter_direction);
-        let scatter = Scatter::new(scattered, self.albedo);
+        let scattered = Ray::with_time(hit_rec.point, scatter_direction, ray.time());
+        // The cosine weighted sampling cancels the cosine and 1/pi of the
+        // reflected light, leaving just the albedo
+        let attenuation = self.albedo.value(hit_rec.u, hit_rec.v, hit_rec.point);
+        let scatter = Scatter::diffuse(scattered, attenuation, local.z / PI);
 
         Some(scatter)
     }
+
+    fn scattering_pdf(&self, _ray: &Ray, hit_rec: &HitRecord, direction: Vec3) -> f32 {
+        let cosine = Vec3::dot(hit_rec.normal, direction.normalized());
+        cosine.max(0.0) / PI
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_rec: &HitRecord, direction: Vec3) -> Color {
+        let albedo = self.albedo.value(hit_rec.u, hit_rec.v, hit_rec.point);
+        albedo * self.scattering_pdf(ray, hit_rec, direction)
+    }
 }
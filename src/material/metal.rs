@@ -1,14 +1,21 @@
 use super::{Material, Scatter};
 
-use crate::{Color, HitRecord, Ray, Vec3};
+use crate::texture::SolidColor;
+use crate::{Color, HitRecord, Ray, SharedTexture, Vec3};
+
+use std::sync::Arc;
 
 pub struct Metal {
-    albedo: Color,
+    albedo: SharedTexture,
     fuzz: f32,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f32) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn textured(albedo: SharedTexture, fuzz: f32) -> Self {
         let fuzz = if fuzz < 1.0 { fuzz } else { 1.0 };
         Self { albedo, fuzz }
     }
@@ -23,8 +30,11 @@ impl Material for Metal {
             return None;
         }
 
-        let scattered = Ray::new(hit_record.point, reflected);
-        let scatter = Scatter::new(scattered, self.albedo);
+        let scattered = Ray::with_time(hit_record.point, reflected, ray.time());
+        let attenuation = self
+            .albedo
+            .value(hit_record.u, hit_record.v, hit_record.point);
+        let scatter = Scatter::specular(scattered, attenuation);
         Some(scatter)
     }
 }
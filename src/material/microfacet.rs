@@ -0,0 +1,165 @@
+//! Building blocks of the microfacet materials. Directions are given in a
+//! local frame with the surface normal along +z, pointing away from the
+//! surface.
+
+use crate::{random, Color, Vec3};
+
+use std::f32::consts::PI;
+
+/// Below this, a surface is treated as perfectly smooth, as the
+/// distribution gets too peaked to evaluate.
+const MIN_ALPHA: f32 = 1e-3;
+
+/// GGX (Trowbridge–Reitz) distribution of microfacet normals with Smith's
+/// masking-shadowing.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    alpha: f32,
+}
+
+impl Ggx {
+    /// 'roughness' in 0..=1 is squared into the distribution's width, which
+    /// makes it change more evenly to the eye.
+    pub fn new(roughness: f32) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Self {
+            alpha: roughness * roughness,
+        }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < MIN_ALPHA
+    }
+
+    /// Density of microfacets facing 'normal', per unit solid angle and
+    /// unit area of the surface.
+    pub fn distribution(&self, normal: Vec3) -> f32 {
+        if normal.z <= 0.0 {
+            return 0.0;
+        }
+        let alpha2 = self.alpha * self.alpha;
+        let denominator = normal.z * normal.z * (alpha2 - 1.0) + 1.0;
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    /// Smith's auxiliary function, the ratio of hidden to visible
+    /// microfacet area seen from 'direction'.
+    fn lambda(&self, direction: Vec3) -> f32 {
+        let cos2 = direction.z * direction.z;
+        if cos2 <= 0.0 {
+            return f32::INFINITY;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        0.5 * ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0)
+    }
+
+    /// Fraction of the microfacets visible from 'direction'.
+    pub fn masking(&self, direction: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(direction))
+    }
+
+    /// Fraction of the microfacets visible from both directions, taking
+    /// into account that high microfacets tend to be visible from both.
+    pub fn masking_shadowing(&self, outgoing: Vec3, incoming: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(outgoing) + self.lambda(incoming))
+    }
+
+    /// Picks a microfacet normal among those visible from 'outgoing', after
+    /// Heitz, "Sampling the GGX Distribution of Visible Normals" (2018).
+    pub fn sample_visible_normal(&self, outgoing: Vec3) -> Vec3 {
+        // Stretch the view so the distribution becomes a hemisphere
+        let view =
+            Vec3::new(self.alpha * outgoing.x, self.alpha * outgoing.y, outgoing.z).normalized();
+        let length2 = view.x * view.x + view.y * view.y;
+        let t1 = match length2 > 0.0 {
+            true => Vec3::new(-view.y, view.x, 0.0) / length2.sqrt(),
+            false => Vec3::new(1.0, 0.0, 0.0),
+        };
+        let t2 = Vec3::cross(view, t1);
+
+        // Point on the projected hemisphere, denser where it's visible
+        let radius = random::random().sqrt();
+        let phi = 2.0 * PI * random::random();
+        let p1 = radius * phi.cos();
+        let blend = 0.5 * (1.0 + view.z);
+        let p2 = (1.0 - blend) * (1.0 - p1 * p1).sqrt() + blend * radius * phi.sin();
+        let p3 = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let normal = p1 * t1 + p2 * t2 + p3 * view;
+
+        Vec3::new(
+            self.alpha * normal.x,
+            self.alpha * normal.y,
+            normal.z.max(1e-6),
+        )
+        .normalized()
+    }
+
+    /// Density with which 'sample_visible_normal' picks 'normal'.
+    pub fn visible_normal_pdf(&self, outgoing: Vec3, normal: Vec3) -> f32 {
+        let cosine = Vec3::dot(outgoing, normal).max(0.0);
+        self.masking(outgoing) * cosine * self.distribution(normal) / outgoing.z
+    }
+}
+
+/// Reflectance of an interface between dielectrics for unpolarized light.
+/// 'eta' is the refraction index on the far side relative to the near
+/// side, and 'cos_incident' the cosine on the near side.
+pub fn fresnel_dielectric(cos_incident: f32, eta: f32) -> f32 {
+    let cos_incident = cos_incident.clamp(0.0, 1.0);
+    let sin2_transmitted = (1.0 - cos_incident * cos_incident) / (eta * eta);
+    if sin2_transmitted >= 1.0 {
+        // Total internal reflection
+        return 1.0;
+    }
+
+    let cos_transmitted = (1.0 - sin2_transmitted).sqrt();
+    let s = (cos_incident - eta * cos_transmitted) / (cos_incident + eta * cos_transmitted);
+    let p = (eta * cos_incident - cos_transmitted) / (eta * cos_incident + cos_transmitted);
+    0.5 * (s * s + p * p)
+}
+
+/// Reflectance of a conductor with the complex refraction index eta + ik,
+/// for each color channel.
+pub fn fresnel_conductor(cos_incident: f32, eta: Color, k: Color) -> Color {
+    let channel = |eta: f32, k: f32| {
+        let cos2 = cos_incident.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let (eta2, k2) = (eta * eta, k * k);
+
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_incident * a;
+        let s = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let p = s * (t3 - t4) / (t3 + t4);
+        0.5 * (s + p)
+    };
+    Color::new(
+        channel(eta.r, k.r),
+        channel(eta.g, k.g),
+        channel(eta.b, k.b),
+    )
+}
+
+/// Mirrors 'direction' at the plane perpendicular to 'normal', both
+/// pointing away from the surface.
+pub fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+    2.0 * Vec3::dot(direction, normal) * normal - direction
+}
+
+/// Refracts 'direction', which points away from the surface on the side of
+/// 'normal', into the other side. 'eta' is the refraction index on the
+/// other side relative to this one. None on total internal reflection.
+pub fn refract(direction: Vec3, normal: Vec3, eta: f32) -> Option<Vec3> {
+    let cos_incident = Vec3::dot(direction, normal);
+    let sin2_transmitted = (1.0 - cos_incident * cos_incident).max(0.0) / (eta * eta);
+    if sin2_transmitted >= 1.0 {
+        return None;
+    }
+    let cos_transmitted = (1.0 - sin2_transmitted).sqrt();
+    Some(-direction / eta + (cos_incident / eta - cos_transmitted) * normal)
+}
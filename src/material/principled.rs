@@ -0,0 +1,286 @@
+use super::microfacet::{self, Ggx};
+use super::{Material, RoughDielectric, Scatter};
+
+use crate::onb::Onb;
+use crate::texture::SolidColor;
+use crate::{random, Color, HitRecord, Ray, SharedTexture, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Rougher lobes keep the highlights of very smooth surfaces finite, the
+/// mixture can't hold a single mirror direction.
+const MIN_ROUGHNESS: f32 = 0.05;
+
+/// The clear coat is a glossy varnish with a fixed roughness.
+const CLEARCOAT_ROUGHNESS: f32 = 0.1;
+
+/// An all-in-one material after Burley, "Physically Based Shading at
+/// Disney" (2012), with the parameters of the Principled BSDF in DCC tools.
+/// It layers a diffuse base with sheen, a GGX specular highlight, colored
+/// rough glass and a clear coat, so a single material covers plastics,
+/// metals, glass and everything in between.
+///
+/// All parameters but the refraction index range from 0 to 1. 'metallic'
+/// blends from a dielectric to a metal tinted by the base color,
+/// 'transmission' from an opaque dielectric to glass. 'specular' scales the
+/// reflectance of dielectrics, 0.5 being the usual 4%.
+pub struct Principled {
+    base_color: SharedTexture,
+    metallic: f32,
+    roughness: f32,
+    specular: f32,
+    sheen: f32,
+    clearcoat: f32,
+    transmission: f32,
+    refraction: f32,
+}
+
+/// Share of each lobe in the material's light for one hit.
+struct Weights {
+    base_color: Color,
+    diffuse: f32,
+    glass: f32,
+    clearcoat: f32,
+}
+
+impl Principled {
+    /// A rough, white-ish plastic with the given base color.
+    pub fn new(base_color: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(base_color)))
+    }
+
+    pub fn textured(base_color: SharedTexture) -> Self {
+        Self {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            sheen: 0.0,
+            clearcoat: 0.0,
+            transmission: 0.0,
+            refraction: 1.5,
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f32) -> Self {
+        self.specular = specular.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Soft, bright rim of cloth at grazing angles.
+    pub fn with_sheen(mut self, sheen: f32) -> Self {
+        self.sheen = sheen.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: f32) -> Self {
+        self.clearcoat = clearcoat.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: f32) -> Self {
+        self.transmission = transmission.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Refraction index of the glass, 1.5 by default.
+    pub fn with_refraction_index(mut self, index_of_refraction: f32) -> Self {
+        self.refraction = index_of_refraction;
+        self
+    }
+
+    fn weights(&self, hit_record: &HitRecord) -> Weights {
+        let dielectric = 1.0 - self.metallic;
+        Weights {
+            base_color: self
+                .base_color
+                .value(hit_record.u, hit_record.v, hit_record.point),
+            diffuse: dielectric * (1.0 - self.transmission),
+            glass: dielectric * self.transmission,
+            clearcoat: 0.25 * self.clearcoat,
+        }
+    }
+
+    fn specular_distribution(&self) -> Ggx {
+        Ggx::new(self.roughness.max(MIN_ROUGHNESS))
+    }
+
+    fn glass(&self) -> RoughDielectric {
+        RoughDielectric::new(self.refraction, self.roughness.max(MIN_ROUGHNESS))
+    }
+
+    /// Reflectance of the specular lobe, shared by the metal and the
+    /// opaque dielectric, with Schlick's approximation of the Fresnel term.
+    fn specular_color(&self, weights: &Weights, cosine: f32) -> Color {
+        let dielectric = 0.08 * self.specular;
+        let dielectric = Color::new(dielectric, dielectric, dielectric);
+        self.metallic * schlick(weights.base_color, cosine)
+            + weights.diffuse * schlick(dielectric, cosine)
+    }
+
+    /// Probabilities of sampling the diffuse, specular, glass and clear
+    /// coat lobes, roughly in proportion to the light they reflect.
+    fn probabilities(&self, weights: &Weights, cos_outgoing: f32) -> [f32; 4] {
+        let clearcoat = schlick(Color::new(0.04, 0.04, 0.04), cos_outgoing).g;
+        let mut probabilities = [
+            weights.diffuse,
+            self.specular_color(weights, cos_outgoing).luminance(),
+            weights.glass,
+            weights.clearcoat * clearcoat,
+        ];
+        let total: f32 = probabilities.iter().sum();
+        if total > 0.0 {
+            probabilities.iter_mut().for_each(|p| *p /= total);
+        }
+        probabilities
+    }
+
+    /// Light scattered from 'direction' back along 'ray', including the
+    /// cosine towards it, and the density of 'scatter' picking it.
+    fn evaluate_lobes(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> (Color, f32) {
+        let mut value = Color::new(0.0, 0.0, 0.0);
+        let mut pdf = 0.0;
+
+        let weights = self.weights(hit_record);
+        let basis = Onb::from_w(hit_record.normal);
+        let outgoing = basis.coordinates(-ray.direction().normalized());
+        let incoming = basis.coordinates(direction.normalized());
+        if outgoing.z <= 0.0 {
+            return (value, pdf);
+        }
+        let probabilities = self.probabilities(&weights, outgoing.z);
+
+        if incoming.z > 0.0 {
+            let half = (outgoing + incoming).normalized();
+            let cos_half = Vec3::dot(outgoing, half);
+
+            // Burley's diffuse darkens or brightens the rims depending on
+            // the roughness, and the sheen adds to them
+            let cos_difference = Vec3::dot(incoming, half);
+            let retro = 0.5 + 2.0 * self.roughness * cos_difference * cos_difference;
+            let rim = |cosine: f32| 1.0 + (retro - 1.0) * schlick_weight(cosine);
+            let diffuse = weights.base_color * (rim(incoming.z) * rim(outgoing.z) / PI)
+                + self.sheen * sheen_color(weights.base_color) * schlick_weight(cos_difference);
+            value += weights.diffuse * diffuse * incoming.z;
+            pdf += probabilities[0] * incoming.z / PI;
+
+            let lobes = [
+                (
+                    self.specular_distribution(),
+                    self.specular_color(&weights, cos_half),
+                    probabilities[1],
+                ),
+                (
+                    Ggx::new(CLEARCOAT_ROUGHNESS),
+                    weights.clearcoat * schlick(Color::new(0.04, 0.04, 0.04), cos_half),
+                    probabilities[3],
+                ),
+            ];
+            for (distribution, fresnel, probability) in lobes {
+                let density = distribution.distribution(half)
+                    * distribution.masking_shadowing(outgoing, incoming);
+                value += fresnel * (density / (4.0 * outgoing.z));
+                pdf += probability * distribution.visible_normal_pdf(outgoing, half)
+                    / (4.0 * cos_half);
+            }
+        }
+
+        if probabilities[2] > 0.0 {
+            let glass = self.glass();
+            let mut transmitted = glass.evaluate(ray, hit_record, direction);
+            if incoming.z < 0.0 {
+                transmitted *= weights.base_color;
+            }
+            value += weights.glass * transmitted;
+            pdf += probabilities[2] * glass.scattering_pdf(ray, hit_record, direction);
+        }
+
+        (value, pdf)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
+        let weights = self.weights(hit_record);
+        let basis = Onb::from_w(hit_record.normal);
+        let outgoing = basis.coordinates(-ray.direction().normalized());
+        if outgoing.z <= 0.0 {
+            return None;
+        }
+        let probabilities = self.probabilities(&weights, outgoing.z);
+
+        let mut choice = random::random();
+        let lobe = probabilities.iter().position(|&probability| {
+            choice -= probability;
+            choice < 0.0
+        })?;
+        let direction = match lobe {
+            0 => {
+                let local = Vec3::random_cosine_direction();
+                basis.local(local.x, local.y, local.z)
+            }
+            2 => self.glass().scatter(ray, hit_record)?.ray.direction(),
+            _ => {
+                let distribution = match lobe {
+                    1 => self.specular_distribution(),
+                    _ => Ggx::new(CLEARCOAT_ROUGHNESS),
+                };
+                let normal = distribution.sample_visible_normal(outgoing);
+                let incoming = microfacet::reflect(outgoing, normal);
+                if incoming.z <= 0.0 {
+                    return None;
+                }
+                basis.local(incoming.x, incoming.y, incoming.z)
+            }
+        };
+
+        let (value, pdf) = self.evaluate_lobes(ray, hit_record, direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let scattered = Ray::with_time(hit_record.point, direction, ray.time());
+        Some(Scatter::diffuse(scattered, value / pdf, pdf))
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        self.evaluate_lobes(ray, hit_record, direction).1
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        self.evaluate_lobes(ray, hit_record, direction).0
+    }
+}
+
+/// Schlick's Fresnel factor, going from 0 facing the viewer to 1 at grazing
+/// angles.
+fn schlick_weight(cosine: f32) -> f32 {
+    (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+/// Schlick's approximation of the reflectance with 'normal' reflectance
+/// when facing the viewer.
+fn schlick(normal: Color, cosine: f32) -> Color {
+    let weight = schlick_weight(cosine);
+    (1.0 - weight) * normal + Color::new(weight, weight, weight)
+}
+
+/// The sheen is halfway between white and the hue of the base color.
+fn sheen_color(base_color: Color) -> Color {
+    let luminance = base_color.luminance();
+    let tint = match luminance > 0.0 {
+        true => base_color / luminance,
+        false => Color::new(1.0, 1.0, 1.0),
+    };
+    0.5 * (tint + Color::new(1.0, 1.0, 1.0))
+}
@@ -0,0 +1,148 @@
+use super::microfacet::{self, Ggx};
+use super::{Material, Scatter};
+
+use crate::onb::Onb;
+use crate::{Color, HitRecord, Ray, Vec3};
+
+/// Metals with a known complex refraction index, sampled at red, green and
+/// blue wavelengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conductor {
+    Aluminium,
+    Copper,
+    Gold,
+    Iron,
+    Silver,
+}
+
+impl Conductor {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "aluminium" | "aluminum" => Some(Self::Aluminium),
+            "copper" => Some(Self::Copper),
+            "gold" => Some(Self::Gold),
+            "iron" => Some(Self::Iron),
+            "silver" => Some(Self::Silver),
+            _ => None,
+        }
+    }
+
+    /// Real part eta and imaginary part k of the refraction index.
+    pub fn refraction_index(self) -> (Color, Color) {
+        let (eta, k) = match self {
+            Self::Aluminium => ([1.6574, 0.8803, 0.5212], [9.2238, 6.2695, 4.8370]),
+            Self::Copper => ([0.2710, 0.6769, 1.3164], [3.6092, 2.6248, 2.2921]),
+            Self::Gold => ([0.1830, 0.4211, 1.3734], [3.4242, 2.3459, 1.7704]),
+            Self::Iron => ([2.9114, 2.9497, 2.5845], [3.0893, 2.9318, 2.7670]),
+            Self::Silver => ([0.1594, 0.1451, 0.1355], [3.9291, 3.1900, 2.3808]),
+        };
+        (
+            Color::new(eta[0], eta[1], eta[2]),
+            Color::new(k[0], k[1], k[2]),
+        )
+    }
+}
+
+/// Metal with a GGX distribution of microfacets, each a perfect mirror with
+/// the Fresnel reflectance of the complex refraction index. Unlike 'Metal',
+/// the tint changes towards white at grazing angles, and lights show up as
+/// highlights rather than being found by chance.
+pub struct RoughConductor {
+    eta: Color,
+    k: Color,
+    distribution: Ggx,
+}
+
+impl RoughConductor {
+    /// 'roughness' goes from 0 for a mirror to 1 for a dull surface.
+    pub fn new(eta: Color, k: Color, roughness: f32) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: Ggx::new(roughness),
+        }
+    }
+
+    pub fn preset(conductor: Conductor, roughness: f32) -> Self {
+        let (eta, k) = conductor.refraction_index();
+        Self::new(eta, k, roughness)
+    }
+
+    /// Directions towards the viewer and the light in the local frame of
+    /// the hit, or None if either lies below the surface.
+    fn local_directions(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        direction: Vec3,
+    ) -> Option<(Vec3, Vec3)> {
+        let basis = Onb::from_w(hit_record.normal);
+        let outgoing = basis.coordinates(-ray.direction().normalized());
+        let incoming = basis.coordinates(direction.normalized());
+        (outgoing.z > 0.0 && incoming.z > 0.0).then_some((outgoing, incoming))
+    }
+}
+
+impl Material for RoughConductor {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
+        let basis = Onb::from_w(hit_record.normal);
+        let outgoing = basis.coordinates(-ray.direction().normalized());
+        if outgoing.z <= 0.0 {
+            return None;
+        }
+
+        if self.distribution.is_smooth() {
+            let incoming = microfacet::reflect(outgoing, Vec3::new(0.0, 0.0, 1.0));
+            let direction = basis.local(incoming.x, incoming.y, incoming.z);
+            let scattered = Ray::with_time(hit_record.point, direction, ray.time());
+            let attenuation = microfacet::fresnel_conductor(outgoing.z, self.eta, self.k);
+            return Some(Scatter::specular(scattered, attenuation));
+        }
+
+        let normal = self.distribution.sample_visible_normal(outgoing);
+        let incoming = microfacet::reflect(outgoing, normal);
+        if incoming.z <= 0.0 {
+            return None;
+        }
+
+        let direction = basis.local(incoming.x, incoming.y, incoming.z);
+        let scattered = Ray::with_time(hit_record.point, direction, ray.time());
+        let pdf = self.distribution.visible_normal_pdf(outgoing, normal)
+            / (4.0 * Vec3::dot(outgoing, normal));
+        // Most terms cancel against the pdf of sampling visible normals
+        let fresnel = microfacet::fresnel_conductor(Vec3::dot(outgoing, normal), self.eta, self.k);
+        let attenuation = fresnel * self.distribution.masking_shadowing(outgoing, incoming)
+            / self.distribution.masking(outgoing);
+
+        Some(Scatter::diffuse(scattered, attenuation, pdf))
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let Some((outgoing, incoming)) = self.local_directions(ray, hit_record, direction) else {
+            return 0.0;
+        };
+
+        let normal = (outgoing + incoming).normalized();
+        self.distribution.visible_normal_pdf(outgoing, normal) / (4.0 * Vec3::dot(outgoing, normal))
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        if self.distribution.is_smooth() {
+            return black;
+        }
+        let Some((outgoing, incoming)) = self.local_directions(ray, hit_record, direction) else {
+            return black;
+        };
+
+        // D G F / (4 cos_o cos_i), times the cosine towards the light
+        let normal = (outgoing + incoming).normalized();
+        let fresnel = microfacet::fresnel_conductor(Vec3::dot(outgoing, normal), self.eta, self.k);
+        let density = self.distribution.distribution(normal)
+            * self.distribution.masking_shadowing(outgoing, incoming);
+        fresnel * (density / (4.0 * outgoing.z))
+    }
+}
@@ -0,0 +1,170 @@
+use super::microfacet::{self, Ggx};
+use super::{Material, Scatter};
+
+use crate::onb::Onb;
+use crate::{random, Color, HitRecord, Ray, Vec3};
+
+/// Frosted glass: a GGX distribution of microfacets, each reflecting or
+/// refracting the light like 'Dialectric', after Walter et al.,
+/// "Microfacet Models for Refraction through Rough Surfaces" (2007).
+///
+/// Like 'Dialectric', the light isn't scaled by the squared ratio of the
+/// refraction indices when it enters the glass, since that cancels out
+/// once it leaves again.
+pub struct RoughDielectric {
+    refraction: f32,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    /// 'roughness' goes from 0 for clear glass to 1 for a strongly
+    /// frosted surface.
+    pub fn new(index_of_refraction: f32, roughness: f32) -> Self {
+        Self {
+            refraction: index_of_refraction,
+            distribution: Ggx::new(roughness),
+        }
+    }
+
+    /// Refraction index on the far side of the surface relative to the side
+    /// the ray comes from.
+    fn relative_refraction(&self, hit_record: &HitRecord) -> f32 {
+        match hit_record.front_face {
+            true => self.refraction,
+            false => 1.0 / self.refraction,
+        }
+    }
+
+    /// Microfacet normal that turns 'outgoing' into 'incoming', on the side
+    /// of the surface normal, and whether the light gets reflected. None if
+    /// no microfacet facing the viewer does it.
+    fn half_vector(&self, outgoing: Vec3, incoming: Vec3, eta: f32) -> Option<(Vec3, bool)> {
+        let reflects = incoming.z > 0.0;
+        let mut normal = match reflects {
+            true => outgoing + incoming,
+            false => outgoing + eta * incoming,
+        };
+        if normal.length_squared() == 0.0 {
+            return None;
+        }
+        normal = normal.normalized();
+        if normal.z < 0.0 {
+            normal = -normal;
+        }
+
+        let facing = Vec3::dot(outgoing, normal) > 0.0;
+        let same_side = (Vec3::dot(incoming, normal) > 0.0) == reflects;
+        (facing && same_side).then_some((normal, reflects))
+    }
+
+    /// Density of 'incoming' per unit solid angle, and the scattered light
+    /// including the cosine towards it.
+    fn pdf_and_value(&self, outgoing: Vec3, incoming: Vec3, eta: f32) -> (f32, f32) {
+        let Some((normal, reflects)) = self.half_vector(outgoing, incoming, eta) else {
+            return (0.0, 0.0);
+        };
+
+        let cos_outgoing = Vec3::dot(outgoing, normal);
+        let fresnel = microfacet::fresnel_dielectric(cos_outgoing, eta);
+        let visible_pdf = self.distribution.visible_normal_pdf(outgoing, normal);
+        let density = self.distribution.distribution(normal)
+            * self.distribution.masking_shadowing(outgoing, incoming);
+
+        match reflects {
+            true => {
+                let pdf = fresnel * visible_pdf / (4.0 * cos_outgoing);
+                let value = fresnel * density / (4.0 * outgoing.z);
+                (pdf, value)
+            }
+            false => {
+                let cos_incoming = Vec3::dot(incoming, normal).abs();
+                let denominator = (cos_outgoing - eta * cos_incoming).powi(2);
+                // Change from the density of microfacet normals to that of
+                // refracted directions
+                let jacobian = eta * eta * cos_incoming / denominator;
+                let pdf = (1.0 - fresnel) * visible_pdf * jacobian;
+                let value = (1.0 - fresnel) * density * cos_outgoing * jacobian / outgoing.z;
+                (pdf, value)
+            }
+        }
+    }
+
+    /// Directions towards the viewer and the light in the local frame of
+    /// the hit.
+    fn local_directions(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> (Vec3, Vec3) {
+        let basis = Onb::from_w(hit_record.normal);
+        (
+            basis.coordinates(-ray.direction().normalized()),
+            basis.coordinates(direction.normalized()),
+        )
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
+        let eta = self.relative_refraction(hit_record);
+        let basis = Onb::from_w(hit_record.normal);
+        let outgoing = basis.coordinates(-ray.direction().normalized());
+        if outgoing.z <= 0.0 {
+            return None;
+        }
+
+        let normal = match self.distribution.is_smooth() {
+            true => Vec3::new(0.0, 0.0, 1.0),
+            false => self.distribution.sample_visible_normal(outgoing),
+        };
+        let fresnel = microfacet::fresnel_dielectric(Vec3::dot(outgoing, normal), eta);
+        let incoming = match fresnel > random::random() {
+            true => microfacet::reflect(outgoing, normal),
+            false => microfacet::refract(outgoing, normal, eta)?,
+        };
+
+        let direction = basis.local(incoming.x, incoming.y, incoming.z);
+        let scattered = Ray::with_time(hit_record.point, direction, ray.time());
+        if self.distribution.is_smooth() {
+            return Some(Scatter::specular(scattered, Color::new(1.0, 1.0, 1.0)));
+        }
+
+        // Reflections going below the surface and refractions going above
+        // it are blocked by neighbouring microfacets
+        let reflects = Vec3::dot(incoming, normal) > 0.0;
+        if (incoming.z > 0.0) != reflects {
+            return None;
+        }
+
+        // The Fresnel term and most others cancel against the pdf
+        let (pdf, _) = self.pdf_and_value(outgoing, incoming, eta);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let attenuation = self.distribution.masking_shadowing(outgoing, incoming)
+            / self.distribution.masking(outgoing);
+        let attenuation = Color::new(attenuation, attenuation, attenuation);
+        Some(Scatter::diffuse(scattered, attenuation, pdf))
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let (outgoing, incoming) = self.local_directions(ray, hit_record, direction);
+        if outgoing.z <= 0.0 {
+            return 0.0;
+        }
+        let eta = self.relative_refraction(hit_record);
+        self.pdf_and_value(outgoing, incoming, eta).0
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let (outgoing, incoming) = self.local_directions(ray, hit_record, direction);
+        if outgoing.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let eta = self.relative_refraction(hit_record);
+        let value = self.pdf_and_value(outgoing, incoming, eta).1;
+        Color::new(value, value, value)
+    }
+}
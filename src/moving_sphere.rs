@@ -0,0 +1,56 @@
+use crate::aabb::Aabb;
+use crate::sphere;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedMaterial, Vec3};
+
+/// Sphere moving in a straight line from 'start' at time 0 to 'end' at time
+/// 1. Before and after, it rests at the respective end of its path.
+pub struct MovingSphere {
+    start: Point3,
+    movement: Vec3,
+    radius: f32,
+    material: SharedMaterial,
+    bounding_box: Aabb,
+}
+
+impl MovingSphere {
+    pub fn new(start: Point3, end: Point3, radius: f32, material: SharedMaterial) -> Self {
+        // The sphere never leaves the boxes at both ends of its path
+        let bounding_box = Aabb::enclosing(
+            sphere::bounding_box(start, radius),
+            sphere::bounding_box(end, radius),
+        );
+
+        Self {
+            start,
+            movement: end - start,
+            radius,
+            material,
+            bounding_box,
+        }
+    }
+
+    pub fn center(&self, time: f32) -> Point3 {
+        self.start + time.clamp(0.0, 1.0) * self.movement
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        sphere::intersect(
+            self.center(ray.time()),
+            self.radius,
+            &self.material,
+            ray,
+            allowed_t,
+            hit_rec,
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
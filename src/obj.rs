@@ -0,0 +1,562 @@
+//! Loader for Wavefront OBJ models and their MTL material libraries.
+
+mod mtl;
+
+use crate::hittable_list::HittableList;
+use crate::triangle_mesh::TriangleMesh;
+use crate::{Color, Lambertian, Point3, SharedMaterial, Vec3};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type ParseResult<T> = Result<T, String>;
+
+/// The triangles of one group that share a material.
+pub struct ObjMesh {
+    pub group: String,
+    pub material_name: Option<String>,
+    pub mesh: TriangleMesh,
+}
+
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+}
+
+impl ObjModel {
+    /// Loads the model at 'path' including the material libraries it
+    /// references. Faces without a known material use a light gray
+    /// Lambertian.
+    pub fn load(path: &Path) -> Result<Self, ObjError> {
+        let source = read(path)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut parser = ObjParser::default();
+
+        for (index, line) in source.lines().enumerate() {
+            parser
+                .parse_line(line, directory)
+                .map_err(|error| error.in_file(path, index + 1))?;
+        }
+
+        Ok(parser.finish())
+    }
+
+    /// Adds all triangles to 'world' separately, so they end up in the same
+    /// BVH as the rest of the scene.
+    pub fn add_to(&self, world: &mut HittableList) {
+        for obj_mesh in &self.meshes {
+            for triangle in obj_mesh.mesh.triangles() {
+                world.add(triangle);
+            }
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.meshes.iter().map(|obj_mesh| obj_mesh.mesh.len()).sum()
+    }
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|error| ObjError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl ObjError {
+    fn invalid(line: usize, message: String) -> Self {
+        Self::Invalid {
+            path: PathBuf::new(),
+            line,
+            message,
+        }
+    }
+
+    /// Fills in where an error without location occured. Errors that
+    /// already have one, e.g. from a material library, are kept as they are.
+    fn in_file(self, path: &Path, line: usize) -> Self {
+        match self {
+            Self::Invalid {
+                path: error_path,
+                line: error_line,
+                message,
+            } if error_path.as_os_str().is_empty() => Self::Invalid {
+                path: path.to_path_buf(),
+                line: if error_line == 0 { line } else { error_line },
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            Self::Invalid {
+                path,
+                line,
+                message,
+            } => write!(f, "{}, line {}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Index of a face corner into the position, texture coordinate and normal
+/// lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Corner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+/// Triangles collected for the current group and material.
+#[derive(Default)]
+struct MeshBuilder {
+    group: String,
+    material_name: Option<String>,
+    corners: Vec<Corner>,
+    corner_indices: HashMap<Corner, u32>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl MeshBuilder {
+    fn new(group: String, material_name: Option<String>) -> Self {
+        Self {
+            group,
+            material_name,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the mesh vertex for the corner, creating it if this
+    /// combination of position, UV and normal hasn't been used yet.
+    fn vertex(&mut self, corner: Corner) -> u32 {
+        let next_index = self.corners.len() as u32;
+        *self.corner_indices.entry(corner).or_insert_with(|| {
+            self.corners.push(corner);
+            next_index
+        })
+    }
+}
+
+#[derive(Default)]
+struct ObjParser {
+    positions: Vec<Point3>,
+    uvs: Vec<(f32, f32)>,
+    normals: Vec<Vec3>,
+    materials: HashMap<String, SharedMaterial>,
+    current: MeshBuilder,
+    finished: Vec<MeshBuilder>,
+}
+
+impl ObjParser {
+    fn parse_line(&mut self, line: &str, directory: &Path) -> Result<(), ObjError> {
+        let error = |message: String| ObjError::invalid(0, message);
+
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            return Ok(());
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        match keyword {
+            "v" => {
+                // The optional weight is only used by rational curves
+                let numbers = parse_numbers(&arguments, 3, 4).map_err(error)?;
+                self.positions
+                    .push(Point3::new(numbers[0], numbers[1], numbers[2]));
+            }
+            "vt" => {
+                let numbers = parse_numbers(&arguments, 1, 3).map_err(error)?;
+                self.uvs
+                    .push((numbers[0], numbers.get(1).copied().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let numbers = parse_numbers(&arguments, 3, 3).map_err(error)?;
+                self.normals
+                    .push(Vec3::new(numbers[0], numbers[1], numbers[2]));
+            }
+            "f" => self.parse_face(&arguments).map_err(error)?,
+            "g" | "o" => {
+                let group = arguments.join(" ");
+                let material_name = self.current.material_name.clone();
+                self.start_mesh(group, material_name);
+            }
+            "usemtl" => {
+                let group = self.current.group.clone();
+                self.start_mesh(group, Some(arguments.join(" ")));
+            }
+            "mtllib" => {
+                // Names with spaces can't be told apart from lists of names,
+                // so every argument is taken as its own file
+                for name in arguments {
+                    let path = directory.join(name);
+                    let source = read(&path)?;
+                    let materials = mtl::parse(&source).map_err(|error| error.in_file(&path, 0))?;
+                    self.materials.extend(materials);
+                }
+            }
+            // Smoothing groups, lines, points and free-form geometry aren't
+            // supported
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn start_mesh(&mut self, group: String, material_name: Option<String>) {
+        let previous = std::mem::replace(&mut self.current, MeshBuilder::new(group, material_name));
+        if !previous.triangles.is_empty() {
+            self.finished.push(previous);
+        }
+    }
+
+    fn parse_face(&mut self, arguments: &[&str]) -> ParseResult<()> {
+        if arguments.len() < 3 {
+            return Err(format!(
+                "a face needs at least three vertices, got {}",
+                arguments.len()
+            ));
+        }
+
+        let corners = arguments
+            .iter()
+            .map(|argument| self.parse_corner(argument))
+            .collect::<ParseResult<Vec<Corner>>>()?;
+
+        let polygon: Vec<Point3> = corners
+            .iter()
+            .map(|corner| self.positions[corner.position])
+            .collect();
+        for [a, b, c] in triangulate(&polygon) {
+            let triangle = [corners[a], corners[b], corners[c]].map(|c| self.current.vertex(c));
+            self.current.triangles.push(triangle);
+        }
+
+        Ok(())
+    }
+
+    /// Parses "v", "v/vt", "v//vn" or "v/vt/vn". Indices start at 1, negative
+    /// ones count backwards from the last element.
+    fn parse_corner(&self, argument: &str) -> ParseResult<Corner> {
+        let mut parts = argument.split('/');
+
+        let resolve =
+            |part: Option<&str>, count: usize, name: &str| -> ParseResult<Option<usize>> {
+                let Some(part) = part.filter(|part| !part.is_empty()) else {
+                    return Ok(None);
+                };
+                let index: i64 = part
+                    .parse()
+                    .map_err(|_| format!("invalid {name} index '{part}'"))?;
+                let resolved = match index {
+                    i if i > 0 => i - 1,
+                    i if i < 0 => count as i64 + i,
+                    _ => return Err(format!("{name} indices start at 1, got 0")),
+                };
+                match (0..count as i64).contains(&resolved) {
+                    true => Ok(Some(resolved as usize)),
+                    false => Err(format!(
+                        "{name} index {index} is out of range, there are {count} so far"
+                    )),
+                }
+            };
+
+        let position = resolve(parts.next(), self.positions.len(), "vertex")?
+            .ok_or_else(|| format!("face vertex '{argument}' has no position"))?;
+        let uv = resolve(parts.next(), self.uvs.len(), "texture coordinate")?;
+        let normal = resolve(parts.next(), self.normals.len(), "normal")?;
+
+        Ok(Corner {
+            position,
+            uv,
+            normal,
+        })
+    }
+
+    fn finish(mut self) -> ObjModel {
+        self.start_mesh(String::new(), None);
+
+        let default_material: SharedMaterial = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+        let meshes = std::mem::take(&mut self.finished)
+            .into_iter()
+            .map(|builder| {
+                let material = builder
+                    .material_name
+                    .as_ref()
+                    .and_then(|name| self.materials.get(name))
+                    .cloned()
+                    .unwrap_or_else(|| Arc::clone(&default_material));
+                self.build_mesh(builder, material)
+            })
+            .collect();
+
+        ObjModel { meshes }
+    }
+
+    fn build_mesh(&self, builder: MeshBuilder, material: SharedMaterial) -> ObjMesh {
+        let positions = builder
+            .corners
+            .iter()
+            .map(|corner| self.positions[corner.position])
+            .collect();
+        let mut mesh = TriangleMesh::new(positions, builder.triangles, material);
+
+        // Attributes that only some corners have are dropped for the whole
+        // mesh
+        let normals: Option<Vec<Vec3>> = builder
+            .corners
+            .iter()
+            .map(|corner| corner.normal.map(|i| self.normals[i].normalized()))
+            .collect();
+        if let Some(normals) = normals {
+            mesh = mesh.with_normals(normals);
+        }
+
+        let uvs: Option<Vec<(f32, f32)>> = builder
+            .corners
+            .iter()
+            .map(|corner| corner.uv.map(|i| self.uvs[i]))
+            .collect();
+        if let Some(uvs) = uvs {
+            mesh = mesh.with_uvs(uvs);
+        }
+
+        ObjMesh {
+            group: builder.group,
+            material_name: builder.material_name,
+            mesh,
+        }
+    }
+}
+
+fn parse_numbers(arguments: &[&str], min: usize, max: usize) -> ParseResult<Vec<f32>> {
+    if arguments.len() < min || arguments.len() > max {
+        return Err(match min == max {
+            true => format!("expected {min} numbers, got {}", arguments.len()),
+            false => format!("expected {min} to {max} numbers, got {}", arguments.len()),
+        });
+    }
+
+    arguments
+        .iter()
+        .map(|argument| {
+            argument
+                .parse()
+                .map_err(|_| format!("'{argument}' is not a number"))
+        })
+        .collect()
+}
+
+/// Splits a planar polygon into triangles by ear clipping, which also
+/// handles concave polygons. Returns indices into 'polygon'.
+fn triangulate(polygon: &[Point3]) -> Vec<[usize; 3]> {
+    if polygon.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Project onto the plane the polygon is most parallel to, using the
+    // normal from Newell's method
+    let mut normal = Vec3::zero();
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+    let (abs_x, abs_y, abs_z) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    let (axes, sign) = if abs_x >= abs_y && abs_x >= abs_z {
+        ([1, 2], normal.x.signum())
+    } else if abs_y >= abs_z {
+        ([2, 0], normal.y.signum())
+    } else {
+        ([0, 1], normal.z.signum())
+    };
+    let coordinate = |p: &Point3, axis: usize| [p.x, p.y, p.z][axis];
+    let points: Vec<(f32, f32)> = polygon
+        .iter()
+        .map(|p| (coordinate(p, axes[0]), coordinate(p, axes[1])))
+        .collect();
+
+    // Positive for corners turning the same way as the whole polygon
+    let turn = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        sign * ((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0))
+    };
+
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            turn(a, b, c) > 0.0
+                && remaining
+                    .iter()
+                    .filter(|&&p| p != a && p != b && p != c)
+                    .all(|&p| turn(a, b, p) < 0.0 || turn(b, c, p) < 0.0 || turn(c, a, p) < 0.0)
+        });
+
+        // Degenerate polygons may have no ear, clip any corner then
+        let i = ear.unwrap_or(0);
+        triangles.push([
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<ObjParser, ObjError> {
+        let mut parser = ObjParser::default();
+        for line in source.lines() {
+            parser.parse_line(line, Path::new(""))?;
+        }
+        Ok(parser)
+    }
+
+    fn error_of(source: &str) -> String {
+        match parse(source) {
+            Ok(_) => panic!("the model parsed without an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    /// Twice the signed area of the triangles in the xy plane.
+    fn doubled_areas(polygon: &[Point3], triangles: &[[usize; 3]]) -> Vec<f32> {
+        triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let (a, b, c) = (polygon[a], polygon[b], polygon[c]);
+                (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn triangulates_concave_polygons() {
+        // An arrow pointing right, concave at the back
+        let arrow: Vec<Point3> = [(0.0, 0.0), (2.0, 1.0), (0.0, 2.0), (0.5, 1.0)]
+            .map(|(x, y)| Point3::new(x, y, 0.0))
+            .to_vec();
+        let triangles = triangulate(&arrow);
+        assert_eq!(triangles.len(), 2);
+        let areas = doubled_areas(&arrow, &triangles);
+        assert!(areas.iter().all(|&area| area > 0.0), "{triangles:?}");
+        assert!((areas.iter().sum::<f32>() - 3.0).abs() < 1e-5);
+
+        // An L shape wound the other way
+        let l_shape: Vec<Point3> = [
+            (0.0, 0.0),
+            (0.0, 2.0),
+            (1.0, 2.0),
+            (1.0, 1.0),
+            (2.0, 1.0),
+            (2.0, 0.0),
+        ]
+        .map(|(x, y)| Point3::new(x, y, 0.0))
+        .to_vec();
+        let triangles = triangulate(&l_shape);
+        assert_eq!(triangles.len(), 4);
+        let areas = doubled_areas(&l_shape, &triangles);
+        assert!(areas.iter().all(|&area| area < 0.0), "{triangles:?}");
+        assert!((areas.iter().sum::<f32>() + 6.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn triangulates_faces() {
+        let parser = parse(
+            "v 0 0 0\nv 2 0 1\nv 0 0 2\nv 0.5 0 1\n\
+             f 1 2 3 4\n\
+             f 1 2 3\n",
+        )
+        .unwrap();
+        assert_eq!(parser.current.triangles.len(), 3);
+        // Corners repeat between faces, so they share the mesh vertices
+        assert_eq!(parser.current.corners.len(), 4);
+
+        let model = parser.finish();
+        assert_eq!(model.triangle_count(), 3);
+    }
+
+    #[test]
+    fn resolves_indices() {
+        let parser = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\n\
+             vn 0 0 1\n\
+             f 1/1/1 2/2/1 3//1\n\
+             v 1 1 0\n\
+             f -4/-2 -3/-1 -1\n",
+        )
+        .unwrap();
+
+        let corner = |position, uv, normal| Corner {
+            position,
+            uv,
+            normal,
+        };
+        assert_eq!(
+            parser.current.corners,
+            [
+                corner(0, Some(0), Some(0)),
+                corner(1, Some(1), Some(0)),
+                corner(2, None, Some(0)),
+                corner(0, Some(0), None),
+                corner(1, Some(1), None),
+                corner(3, None, None),
+            ]
+        );
+        assert_eq!(parser.current.triangles, [[0, 1, 2], [3, 4, 5]]);
+    }
+
+    #[test]
+    fn rejects_invalid_indices() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\n";
+        let error = |face: &str| error_of(&format!("{vertices}{face}"));
+
+        assert!(error("f 1 2 4").ends_with("vertex index 4 is out of range, there are 3 so far"));
+        assert!(error("f -4 1 2").ends_with("vertex index -4 is out of range, there are 3 so far"));
+        assert!(error("f 0 1 2").ends_with("vertex indices start at 1, got 0"));
+        assert!(error("f 1/2 2 3").contains("texture coordinate index 2 is out of range"));
+        assert!(error("f 1//1 2 3").contains("normal index 1 is out of range"));
+        assert!(error("f 1 2 x").ends_with("invalid vertex index 'x'"));
+        assert!(error("f /1 2 3").ends_with("face vertex '/1' has no position"));
+        assert!(error("f 1 2").ends_with("a face needs at least three vertices, got 2"));
+    }
+}
@@ -0,0 +1,183 @@
+use super::{ObjError, ParseResult};
+
+use crate::{Color, Dialectric, DiffuseLight, Lambertian, Metal, Principled, SharedMaterial};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The parameters of a material definition that can be mapped onto the
+/// crate's materials.
+struct MtlMaterial {
+    diffuse: Color,
+    specular: Color,
+    emission: Color,
+    specular_exponent: f32,
+    refraction_index: f32,
+    dissolve: f32,
+    illumination_model: u32,
+    roughness: Option<f32>,
+    metallic: Option<f32>,
+    sheen: Option<f32>,
+    clearcoat: Option<f32>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0.0, 0.0, 0.0),
+            emission: Color::new(0.0, 0.0, 0.0),
+            specular_exponent: 0.0,
+            refraction_index: 1.5,
+            dissolve: 1.0,
+            illumination_model: 2,
+            roughness: None,
+            metallic: None,
+            sheen: None,
+            clearcoat: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Emissive materials become lights. Materials from PBR exporters,
+    /// with any of the PBR parameters, become principled materials with the
+    /// diffuse color as the base color. Otherwise transparent ones become
+    /// glass, and reflective ones metal. Everything else is diffuse.
+    fn into_material(self) -> SharedMaterial {
+        let is_black = |color: Color| color.r <= 0.0 && color.g <= 0.0 && color.b <= 0.0;
+
+        if !is_black(self.emission) {
+            return Arc::new(DiffuseLight::new(self.emission));
+        }
+
+        let transparent = self.dissolve < 1.0 || matches!(self.illumination_model, 4 | 6 | 7 | 9);
+        let is_pbr = self.roughness.is_some()
+            || self.metallic.is_some()
+            || self.sheen.is_some()
+            || self.clearcoat.is_some();
+        if is_pbr {
+            let transmission = match transparent {
+                true => 1.0,
+                false => 0.0,
+            };
+            return Arc::new(
+                Principled::new(self.diffuse)
+                    .with_metallic(self.metallic.unwrap_or(0.0))
+                    .with_roughness(self.fuzz())
+                    .with_sheen(self.sheen.unwrap_or(0.0))
+                    .with_clearcoat(self.clearcoat.unwrap_or(0.0))
+                    .with_transmission(transmission)
+                    .with_refraction_index(self.refraction_index),
+            );
+        }
+
+        if transparent {
+            return Arc::new(Dialectric::new(self.refraction_index));
+        }
+
+        if self.illumination_model == 3 && !is_black(self.specular) {
+            return Arc::new(Metal::new(self.specular, self.fuzz()));
+        }
+
+        Arc::new(Lambertian::new(self.diffuse))
+    }
+
+    /// Uses the PBR roughness if given, otherwise converts the Phong
+    /// specular exponent to a comparable roughness.
+    fn fuzz(&self) -> f32 {
+        let roughness = self
+            .roughness
+            .unwrap_or_else(|| (2.0 / (self.specular_exponent + 2.0)).sqrt());
+        roughness.clamp(0.0, 1.0)
+    }
+}
+
+pub fn parse(source: &str) -> Result<HashMap<String, SharedMaterial>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: String| ObjError::invalid(line_number, message);
+
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material.into_material());
+            }
+            let name = arguments.join(" ");
+            if name.is_empty() {
+                return Err(error("'newmtl' needs a name".to_string()));
+            }
+            current = Some((name, MtlMaterial::default()));
+            continue;
+        }
+
+        let Some((_, material)) = current.as_mut() else {
+            return Err(error(format!("'{keyword}' before the first 'newmtl'")));
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = parse_color(&arguments).map_err(error)?,
+            "Ks" => material.specular = parse_color(&arguments).map_err(error)?,
+            "Ke" => material.emission = parse_color(&arguments).map_err(error)?,
+            "Ns" => material.specular_exponent = parse_number(&arguments).map_err(error)?,
+            "Ni" => material.refraction_index = parse_number(&arguments).map_err(error)?,
+            "d" => material.dissolve = parse_number(&arguments).map_err(error)?,
+            "Tr" => material.dissolve = 1.0 - parse_number(&arguments).map_err(error)?,
+            "illum" => {
+                material.illumination_model = parse_number(&arguments).map_err(error)? as u32
+            }
+            "Pr" => material.roughness = Some(parse_number(&arguments).map_err(error)?),
+            "Pm" => material.metallic = Some(parse_number(&arguments).map_err(error)?),
+            "Ps" => material.sheen = Some(parse_number(&arguments).map_err(error)?),
+            "Pc" => material.clearcoat = Some(parse_number(&arguments).map_err(error)?),
+            // Ambient color, transmission filter, texture maps and other
+            // extensions have no equivalent
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material.into_material());
+    }
+
+    Ok(materials)
+}
+
+fn parse_number(arguments: &[&str]) -> ParseResult<f32> {
+    match arguments {
+        [number] => number
+            .parse()
+            .map_err(|_| format!("'{number}' is not a number")),
+        _ => Err(format!("expected one number, got {}", arguments.len())),
+    }
+}
+
+fn parse_color(arguments: &[&str]) -> ParseResult<Color> {
+    let numbers = arguments
+        .iter()
+        .map(|argument| {
+            argument
+                .parse()
+                .map_err(|_| format!("'{argument}' is not a number"))
+        })
+        .collect::<ParseResult<Vec<f32>>>()?;
+
+    // A single value is used for all three components
+    match numbers.as_slice() {
+        [value] => Ok(Color::new(*value, *value, *value)),
+        [r, g, b] => Ok(Color::new(*r, *g, *b)),
+        _ => Err(format!(
+            "expected a color of one or three numbers, got {}",
+            numbers.len()
+        )),
+    }
+}
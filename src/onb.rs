@@ -0,0 +1,39 @@
+use crate::Vec3;
+
+/// Orthonormal basis, with 'w' usually along a surface normal.
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Completes the basis around 'w', which doesn't need to be normalized.
+    pub fn from_w(w: Vec3) -> Self {
+        let w = w.normalized();
+        // Any vector that isn't (nearly) parallel to w will do
+        let helper = match w.x.abs() > 0.9 {
+            true => Vec3::new(0.0, 1.0, 0.0),
+            false => Vec3::new(1.0, 0.0, 0.0),
+        };
+        let v = Vec3::cross(w, helper).normalized();
+        let u = Vec3::cross(v, w);
+        Self { u, v, w }
+    }
+
+    /// Converts coordinates in this basis to a vector in world space.
+    pub fn local(&self, a: f32, b: f32, c: f32) -> Vec3 {
+        a * self.u + b * self.v + c * self.w
+    }
+
+    /// Converts a world space vector to coordinates in this basis, the
+    /// inverse of 'local'.
+    pub fn coordinates(&self, vector: Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot(vector, self.u),
+            Vec3::dot(vector, self.v),
+            Vec3::dot(vector, self.w),
+        )
+    }
+}
@@ -0,0 +1,103 @@
+//! Gradient noise after Ken Perlin, as described in "Ray Tracing: The Next
+//! Week".
+
+use crate::{random, Point3, Vec3};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+const POINT_COUNT: usize = 256;
+
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    permutations: [[u8; POINT_COUNT]; 3],
+}
+
+impl Perlin {
+    /// Noise with the same 'seed' is identical.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = random::generator(seed);
+
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                let vector = Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                );
+                // Avoid normalizing the rare vector of (nearly) zero length
+                match vector.near_zero() {
+                    true => Vec3::new(1.0, 0.0, 0.0),
+                    false => vector.normalized(),
+                }
+            })
+            .collect();
+
+        let permutations = [(); 3].map(|_| {
+            let mut permutation = [0; POINT_COUNT];
+            for (i, value) in permutation.iter_mut().enumerate() {
+                *value = i as u8;
+            }
+            permutation.shuffle(&mut rng);
+            permutation
+        });
+
+        Self {
+            gradients,
+            permutations,
+        }
+    }
+
+    /// Smooth noise in -1..=1, repeating every 256 units.
+    pub fn noise(&self, point: Point3) -> f32 {
+        let floor = [point.x.floor(), point.y.floor(), point.z.floor()];
+        let fraction = [point.x - floor[0], point.y - floor[1], point.z - floor[2]];
+        let cell = floor.map(|coordinate| coordinate as i64);
+
+        // Hermite smoothing hides the grid in the interpolation
+        let [u, v, w] = fraction.map(|t| t * t * (3.0 - 2.0 * t));
+
+        let mut sum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradient(cell[0] + di, cell[1] + dj, cell[2] + dk);
+                    let offset = Vec3::new(
+                        fraction[0] - di as f32,
+                        fraction[1] - dj as f32,
+                        fraction[2] - dk as f32,
+                    );
+
+                    let weight_u = if di == 1 { u } else { 1.0 - u };
+                    let weight_v = if dj == 1 { v } else { 1.0 - v };
+                    let weight_w = if dk == 1 { w } else { 1.0 - w };
+                    sum += weight_u * weight_v * weight_w * Vec3::dot(gradient, offset);
+                }
+            }
+        }
+        sum
+    }
+
+    /// Sum of 'depth' octaves of the absolute noise, each at double the
+    /// frequency and half the weight of the one before.
+    pub fn turbulence(&self, point: Point3, depth: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut point = point;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            sum += weight * self.noise(point).abs();
+            weight *= 0.5;
+            point = Point3::new(2.0 * point.x, 2.0 * point.y, 2.0 * point.z);
+        }
+
+        sum
+    }
+
+    /// Hashes the lattice point (i, j, k) to one of the random gradients.
+    fn gradient(&self, i: i64, j: i64, k: i64) -> Vec3 {
+        let [x, y, z] = &self.permutations;
+        let index = x[(i & 255) as usize] ^ y[(j & 255) as usize] ^ z[(k & 255) as usize];
+        self.gradients[index as usize]
+    }
+}
@@ -0,0 +1,107 @@
+use crate::aabb::Aabb;
+use crate::interval::{self, Interval};
+use crate::onb::Onb;
+use crate::triangle::BOUNDING_BOX_PADDING;
+use crate::{HitRecord, Hittable, Point3, Ray, SharedMaterial, Vec3};
+
+use std::sync::Arc;
+
+/// Infinite plane through 'point'. Its front faces the side the normal
+/// points to. The texture coordinates are the distances from 'point' along
+/// two directions within the plane, so textures repeat across it.
+pub struct Plane {
+    point: Point3,
+    basis: Onb,
+    material: SharedMaterial,
+    bounding_box: Aabb,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: SharedMaterial) -> Self {
+        let basis = Onb::from_w(normal);
+
+        // Only planes perpendicular to an axis are bounded along that axis
+        let axis = |normal: f32, coordinate: f32| match normal.abs() == 1.0 {
+            true => Interval::new(coordinate, coordinate).expanded(BOUNDING_BOX_PADDING),
+            false => interval::REAL_LINE,
+        };
+        let w = basis.w;
+        let bounding_box = Aabb::new(axis(w.x, point.x), axis(w.y, point.y), axis(w.z, point.z));
+
+        Self {
+            point,
+            basis,
+            material,
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let Some((t, point)) = intersect(ray, self.point, self.basis.w, allowed_t) else {
+            return false;
+        };
+
+        let offset = point - self.point;
+        hit_rec.u = Vec3::dot(offset, self.basis.u);
+        hit_rec.v = Vec3::dot(offset, self.basis.v);
+        fill_hit_record(hit_rec, ray, t, point, self.basis.w, &self.material);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+/// Intersection with the plane through 'origin' with the given normal,
+/// which doesn't need to be normalized. Returns the ray parameter and the
+/// hit point.
+pub(crate) fn intersect(
+    ray: &Ray,
+    origin: Point3,
+    normal: Vec3,
+    allowed_t: Interval,
+) -> Option<(f32, Point3)> {
+    let denominator = Vec3::dot(normal, ray.direction());
+    // The ray is parallel to the plane
+    if denominator.abs() < 1e-8 {
+        return None;
+    }
+
+    let t = Vec3::dot(normal, origin - ray.origin()) / denominator;
+    match allowed_t.surrounds(t) {
+        true => Some((t, ray.at(t))),
+        false => None,
+    }
+}
+
+/// Converts the density of points picked uniformly on a flat object of
+/// 'area' into density per solid angle, for a ray from 'origin' along
+/// 'direction' that hits the object at 't'.
+pub(crate) fn solid_angle_pdf(direction: Vec3, t: f32, normal: Vec3, area: f32) -> f32 {
+    let distance_squared = t * t * direction.length_squared();
+    let cosine = (Vec3::dot(direction, normal) / direction.length()).abs();
+    match cosine > 1e-8 {
+        true => distance_squared / (cosine * area),
+        false => 0.0,
+    }
+}
+
+/// Fills everything but the texture coordinates for a hit on a flat
+/// surface with the given unit normal.
+pub(crate) fn fill_hit_record(
+    hit_rec: &mut HitRecord,
+    ray: &Ray,
+    t: f32,
+    point: Point3,
+    normal: Vec3,
+    material: &SharedMaterial,
+) {
+    hit_rec.t = t;
+    hit_rec.point = point;
+    hit_rec.set_face_normal(ray, normal);
+    hit_rec.material = Some(Arc::clone(material));
+}
@@ -0,0 +1,89 @@
+use crate::aabb::Aabb;
+use crate::plane;
+use crate::random;
+use crate::triangle::BOUNDING_BOX_PADDING;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedMaterial, Vec3};
+
+/// Parallelogram spanned by two edges from a corner. The front face is the
+/// one from which 'u' turns counter clockwise into 'v'.
+pub struct Quad {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    /// Maps points in the plane to coordinates along u and v
+    w: Vec3,
+    area: f32,
+    material: SharedMaterial,
+    bounding_box: Aabb,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: SharedMaterial) -> Self {
+        let n = Vec3::cross(u, v);
+        let bounding_box = Aabb::enclosing(
+            Aabb::from_points(corner, corner + u + v),
+            Aabb::from_points(corner + u, corner + v),
+        )
+        .padded(BOUNDING_BOX_PADDING);
+
+        Self {
+            corner,
+            u,
+            v,
+            normal: n.normalized(),
+            w: n / n.length_squared(),
+            area: n.length(),
+            material,
+            bounding_box,
+        }
+    }
+
+    pub fn corner(&self) -> Point3 {
+        self.corner
+    }
+
+    pub fn edges(&self) -> (Vec3, Vec3) {
+        (self.u, self.v)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let Some((t, point)) = plane::intersect(ray, self.corner, self.normal, allowed_t) else {
+            return false;
+        };
+
+        // Coordinates of the hit point along the edges, the corners being
+        // (0, 0) and (1, 1)
+        let offset = point - self.corner;
+        let alpha = Vec3::dot(self.w, Vec3::cross(offset, self.v));
+        let beta = Vec3::dot(self.w, Vec3::cross(self.u, offset));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        hit_rec.u = alpha;
+        hit_rec.v = beta;
+        plane::fill_hit_record(hit_rec, ray, t, point, self.normal, &self.material);
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let ray = Ray::new(origin, direction);
+        match self.hit(&ray, Interval::new(0.001, f32::INFINITY)) {
+            Some(hit) => plane::solid_angle_pdf(direction, hit.t, self.normal, self.area),
+            None => 0.0,
+        }
+    }
+
+    fn random_direction(&self, origin: Point3) -> Vec3 {
+        let point = self.corner + random::random() * self.u + random::random() * self.v;
+        point - origin
+    }
+}
@@ -1,27 +1,56 @@
-use rand::{self, rngs::ThreadRng, Rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-static mut RNG: Option<ThreadRng> = None;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-/// This function should be called before any of the other functions of this
-/// module are called.
-pub fn initialize() {
-    unsafe {
-        RNG = Some(rand::thread_rng());
-    }
+static SEED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Every thread owns its generator, threads that never reseed start from
+    // the global seed.
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(seed()));
+}
+
+/// Sets the global seed and restarts the calling thread's generator from it.
+/// Should be called before building the scene, so the scene and the render
+/// are reproducible.
+pub fn set_seed(seed: u64) {
+    SEED.store(seed, Ordering::Relaxed);
+    reseed(0);
+}
+
+pub fn seed() -> u64 {
+    SEED.load(Ordering::Relaxed)
+}
+
+/// Restarts the calling thread's generator with a sequence derived from the
+/// global seed and 'stream'. The renderer does this for every pixel, so the
+/// numbers a pixel gets don't depend on which thread renders it.
+pub fn reseed(stream: u64) {
+    let new_rng = StdRng::seed_from_u64(mix(seed(), stream));
+    RNG.with(|rng| *rng.borrow_mut() = new_rng);
+}
+
+/// A generator of its own, independent of the global seed and the calling
+/// thread. For data like noise tables that has to come out the same no
+/// matter when or where it's built.
+pub fn generator(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(mix(seed, 0))
 }
 
-/// Before calling this function, "initialize" should have been called before.
 pub fn random() -> f32 {
-    unsafe {
-        // The unwrap works as "initialize" should have been called at this point
-        RNG.as_mut().unwrap().gen()
-    }
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
 
-/// Before calling this function, "initialize" should have been called before.
 pub fn random_range(min: f32, max: f32) -> f32 {
-    unsafe {
-        // The unwrap works as "initialize" should have been called at this point
-        RNG.as_mut().unwrap().gen_range(min..max)
-    }
+    RNG.with(|rng| rng.borrow_mut().gen_range(min..max))
+}
+
+/// SplitMix64 finalizer, so that neighbouring streams get unrelated seeds.
+fn mix(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
@@ -3,11 +3,22 @@ use crate::{Point3, Vec3};
 pub struct Ray {
     origin: Point3,
     direction: Vec3,
+    time: f32,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self::with_time(origin, direction, 0.0)
+    }
+
+    /// Ray at the given moment, so that moving objects can be hit where they
+    /// are at that time.
+    pub fn with_time(origin: Point3, direction: Vec3, time: f32) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn origin(&self) -> Point3 {
@@ -18,6 +29,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
     pub fn at(&self, t: f32) -> Point3 {
         self.origin + t * self.direction
     }
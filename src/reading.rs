@@ -0,0 +1,86 @@
+//! Decoders for the image files used as textures and environment maps.
+
+mod hdr;
+mod inflate;
+mod pfm;
+mod png;
+mod ppm;
+
+use crate::Color;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Decoders refuse images with more pixels than this, far more than any
+/// texture or environment map needs, so corrupt headers can't make them
+/// allocate huge buffers.
+const MAX_PIXELS: usize = 1 << 28;
+
+/// An image as stored in its file. Low dynamic range formats have their
+/// components scaled to 0..=1 but still in the file's color encoding,
+/// usually sRGB. High dynamic range formats hold linear values.
+#[derive(Debug)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    /// The rows from top to bottom
+    pub pixels: Vec<Color>,
+    /// Whether the file stores linear values rather than sRGB encoded ones
+    pub is_linear: bool,
+}
+
+/// Reads a PPM (P3 or P6), PNG, Radiance HDR or PFM file, telling them apart
+/// by their contents rather than the extension.
+pub fn read_image(path: &Path) -> Result<DecodedImage, ImageError> {
+    let bytes = fs::read(path).map_err(|error| ImageError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    let decoded = if bytes.starts_with(&crate::writing::png::SIGNATURE) {
+        png::decode(&bytes)
+    } else if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+        ppm::decode(&bytes)
+    } else if bytes.starts_with(b"#?RADIANCE") || bytes.starts_with(b"#?RGBE") {
+        hdr::decode(&bytes)
+    } else if bytes.starts_with(b"PF") || bytes.starts_with(b"Pf") {
+        pfm::decode(&bytes)
+    } else {
+        Err("unsupported image format, expected a PPM (P3 or P6), PNG, HDR or PFM file".to_string())
+    };
+
+    decoded.map_err(|message| ImageError::Invalid {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// Number of pixels of an image with the given size, unless it's empty or
+/// too large to decode.
+pub(crate) fn pixel_count(width: usize, height: usize) -> Result<usize, String> {
+    match width.checked_mul(height) {
+        Some(count) if count > 0 && count <= MAX_PIXELS => Ok(count),
+        _ => Err(format!("image has an unsupported size of {width}x{height}")),
+    }
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io { path: PathBuf, error: io::Error },
+    Invalid { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => {
+                write!(f, "could not read image {}: {}", path.display(), error)
+            }
+            Self::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
@@ -0,0 +1,305 @@
+use super::{pixel_count, DecodedImage};
+
+use crate::Color;
+
+/// Scanlines of this width range may be stored with the adaptive run length
+/// encoding.
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+
+/// Decodes a Radiance RGBE (.hdr) file, with flat, old style run length
+/// encoded or adaptive run length encoded scanlines.
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let mut position = 0;
+    let mut next_line = || {
+        let rest = bytes.get(position..).unwrap_or_default();
+        let length = rest.iter().position(|&byte| byte == b'\n')?;
+        position += length + 1;
+        Some(String::from_utf8_lossy(&rest[..length]).into_owned())
+    };
+
+    // The header ends with an empty line, the resolution follows it
+    loop {
+        let line = next_line().ok_or("header isn't terminated by an empty line")?;
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format.trim() != "32-bit_rle_rgbe" {
+                return Err(format!("unsupported pixel format '{}'", format.trim()));
+            }
+        }
+    }
+
+    let resolution = next_line().ok_or("header is missing the resolution")?;
+    let (width, height, bottom_up) = parse_resolution(&resolution)?;
+    pixel_count(width, height)?;
+    let data = &bytes[position..];
+    // Each scanline takes at least one pixel or run length header
+    if data.len() / 4 < height {
+        return Err(format!(
+            "pixel data is truncated, expected {height} scanlines"
+        ));
+    }
+
+    let mut reader = ScanlineReader { data, position: 0 };
+    let mut rows = Vec::new();
+    for row in 0..height {
+        let scanline = reader
+            .scanline(width)
+            .map_err(|message| format!("scanline {row}: {message}"))?;
+        rows.push(scanline);
+    }
+    if bottom_up {
+        rows.reverse();
+    }
+
+    let pixels = rows.into_iter().flatten().map(from_rgbe).collect();
+    Ok(DecodedImage {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+        is_linear: true,
+    })
+}
+
+/// Parses the standard orientation "-Y height +X width", or "+Y height +X
+/// width" for images stored from the bottom row up.
+fn parse_resolution(line: &str) -> Result<(usize, usize, bool), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (bottom_up, height, width) = match words.as_slice() {
+        ["-Y", height, "+X", width] => (false, height, width),
+        ["+Y", height, "+X", width] => (true, height, width),
+        _ => return Err(format!("unsupported resolution line '{line}'")),
+    };
+
+    let parse = |number: &str| number.parse::<usize>().ok().filter(|&n| n > 0);
+    match (parse(width), parse(height)) {
+        (Some(width), Some(height)) => Ok((width, height, bottom_up)),
+        _ => Err(format!("image has an invalid size in '{line}'")),
+    }
+}
+
+struct ScanlineReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl ScanlineReader<'_> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = self
+            .data
+            .get(self.position)
+            .copied()
+            .ok_or("pixel data is truncated")?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn pixel(&mut self) -> Result<[u8; 4], String> {
+        Ok([self.byte()?, self.byte()?, self.byte()?, self.byte()?])
+    }
+
+    fn scanline(&mut self, width: usize) -> Result<Vec<[u8; 4]>, String> {
+        let is_adaptive = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width)
+            && self.data.get(self.position..self.position + 2) == Some(&[2, 2]);
+        match is_adaptive {
+            true => self.adaptive_scanline(width),
+            false => self.flat_scanline(width),
+        }
+    }
+
+    /// Scanline starting with the bytes 2, 2 and its width, followed by the
+    /// four components one after the other, each stored as runs and
+    /// literal sequences.
+    fn adaptive_scanline(&mut self, width: usize) -> Result<Vec<[u8; 4]>, String> {
+        let header = self.pixel()?;
+        let encoded_width = (header[2] as usize) << 8 | header[3] as usize;
+        if encoded_width != width {
+            return Err(format!(
+                "scanline is {encoded_width} pixels wide instead of {width}"
+            ));
+        }
+
+        let mut scanline = vec![[0; 4]; width];
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.byte()? as usize;
+                let (length, is_run) = match count > 128 {
+                    true => (count - 128, true),
+                    false => (count, false),
+                };
+                if length == 0 || x + length > width {
+                    return Err("run length encoding overruns the scanline".to_string());
+                }
+
+                match is_run {
+                    true => {
+                        let value = self.byte()?;
+                        for pixel in &mut scanline[x..x + length] {
+                            pixel[channel] = value;
+                        }
+                    }
+                    false => {
+                        for pixel in &mut scanline[x..x + length] {
+                            pixel[channel] = self.byte()?;
+                        }
+                    }
+                }
+                x += length;
+            }
+        }
+
+        Ok(scanline)
+    }
+
+    /// Plain RGBE pixels, where a pixel of 1, 1, 1 repeats the previous one
+    /// as often as its exponent says. Consecutive repeat pixels make up the
+    /// higher bytes of the count.
+    fn flat_scanline(&mut self, width: usize) -> Result<Vec<[u8; 4]>, String> {
+        let mut scanline: Vec<[u8; 4]> = Vec::new();
+        let mut shift = 0;
+        while scanline.len() < width {
+            let pixel = self.pixel()?;
+            match (pixel, scanline.last().copied()) {
+                ([1, 1, 1, count], Some(previous)) => {
+                    let repeat = match count {
+                        0 => None,
+                        _ => (count as usize)
+                            .checked_shl(shift)
+                            .filter(|repeat| repeat >> shift == count as usize),
+                    };
+                    let Some(repeat) = repeat.filter(|&n| scanline.len() + n <= width) else {
+                        return Err("run length is zero or overruns the scanline".to_string());
+                    };
+                    scanline.extend(std::iter::repeat_n(previous, repeat));
+                    shift += 8;
+                }
+                _ => {
+                    scanline.push(pixel);
+                    shift = 0;
+                }
+            }
+        }
+        Ok(scanline)
+    }
+}
+
+/// The mantissas share the exponent in the fourth byte. Adding a half
+/// reconstructs the middle of the range the writer truncated.
+fn from_rgbe([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let scale = 2f32.powi(e as i32 - 136);
+    Color::new(
+        (r as f32 + 0.5) * scale,
+        (g as f32 + 0.5) * scale,
+        (b as f32 + 0.5) * scale,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writing::hdr;
+
+    const HEADER: &[u8] = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n";
+
+    fn file(resolution: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = HEADER.to_vec();
+        bytes.extend_from_slice(resolution.as_bytes());
+        bytes.push(b'\n');
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn test_pixels(count: usize) -> Vec<Color> {
+        (0..count)
+            .map(|i| Color::new(i as f32 * 0.25, 2f32.powi(i as i32 % 9 - 4), 100.0))
+            .collect()
+    }
+
+    fn assert_close(decoded: &[Color], expected: &[Color]) {
+        assert_eq!(decoded.len(), expected.len());
+        for (a, b) in decoded.iter().zip(expected) {
+            // RGBE keeps 8 bits of the largest component
+            let tolerance = b.r.max(b.g).max(b.b) / 128.0;
+            for (x, y) in [(a.r, b.r), (a.g, b.g), (a.b, b.b)] {
+                assert!((x - y).abs() <= tolerance, "{a:?} isn't close to {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn reads_what_the_writer_writes() {
+        // Wide enough for the adaptive run length encoding, and too narrow
+        for (width, height) in [(12, 3), (3, 2)] {
+            let pixels = test_pixels(width * height);
+            let mut bytes = Vec::new();
+            hdr::write(&mut bytes, width as u32, height as u32, &pixels).unwrap();
+
+            let image = decode(&bytes).unwrap();
+            assert_eq!((image.width, image.height), (width as u32, height as u32));
+            assert!(image.is_linear);
+            assert_close(&image.pixels, &pixels);
+        }
+    }
+
+    #[test]
+    fn reads_old_run_lengths_and_bottom_up_images() {
+        let image = decode(&file("-Y 1 +X 4", &[128, 64, 0, 129, 1, 1, 1, 3])).unwrap();
+        assert_close(&image.pixels, &[Color::new(1.0, 0.5, 0.0); 4]);
+
+        let image = decode(&file("+Y 2 +X 1", &[128, 0, 0, 129, 0, 128, 0, 129])).unwrap();
+        assert_close(
+            &image.pixels,
+            &[Color::new(0.0, 1.0, 0.0), Color::new(1.0, 0.0, 0.0)],
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_run_lengths() {
+        let mut data = vec![128, 64, 0, 129];
+        for _ in 0..8 {
+            data.extend_from_slice(&[1, 1, 1, 0]);
+        }
+        assert!(decode(&file("-Y 1 +X 4", &data)).is_err());
+
+        let mut data = vec![128, 64, 0, 129];
+        for _ in 0..9 {
+            data.extend_from_slice(&[1, 1, 1, 1]);
+        }
+        assert!(decode(&file("-Y 1 +X 4", &data)).is_err());
+
+        // An adaptive run past the end of the scanline
+        let data = [2, 2, 0, 8, 130 + 8, 0];
+        assert!(decode(&file("-Y 1 +X 8", &data)).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_images() {
+        let huge = file("-Y 1 +X 9223372036854775807", &[0; 4]);
+        assert!(decode(&huge).unwrap_err().contains("unsupported size"));
+
+        let tall = file("-Y 1000000 +X 1", &[0; 16]);
+        assert!(decode(&tall).unwrap_err().contains("truncated"));
+    }
+
+    #[test]
+    fn rejects_invalid_headers_and_truncated_data() {
+        assert!(decode(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n").is_err());
+        assert!(decode(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0").is_err());
+        assert!(decode(&file("+X 1 -Y 1", &[0; 4])).is_err());
+        assert!(decode(&file("-Y 0 +X 1", &[])).is_err());
+
+        let mut bytes = Vec::new();
+        hdr::write(&mut bytes, 12, 3, &test_pixels(36)).unwrap();
+        let header_length = HEADER.len() + "-Y 3 +X 12\n".len();
+        for length in header_length..bytes.len() {
+            assert!(decode(&bytes[..length]).is_err(), "length {length}");
+        }
+    }
+}
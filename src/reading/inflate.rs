@@ -0,0 +1,369 @@
+//! Minimal zlib (RFC 1950) decoder, supporting all three kinds of deflate
+//! (RFC 1951) blocks: stored, fixed Huffman codes and dynamic Huffman codes.
+
+use crate::writing::zlib::adler32;
+
+const MAX_CODE_LENGTH: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which the code lengths of the code length alphabet are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let [cmf, flg, ..] = *data else {
+        return Err("compressed data is too short".to_string());
+    };
+    if cmf & 0x0f != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err("compressed data has an invalid zlib header".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("compressed data needs a preset dictionary".to_string());
+    }
+
+    let mut reader = BitReader::new(&data[2..]);
+    let mut out = Vec::new();
+    loop {
+        let is_final = reader.read_bits(1)? == 1;
+        match reader.read_bits(2)? {
+            0 => inflate_stored(&mut reader, &mut out)?,
+            1 => inflate_huffman(&mut reader, &mut out, &fixed_codes())?,
+            2 => {
+                let codes = read_dynamic_codes(&mut reader)?;
+                inflate_huffman(&mut reader, &mut out, &codes)?;
+            }
+            _ => return Err("compressed data has an invalid block type".to_string()),
+        }
+        if is_final {
+            break;
+        }
+    }
+
+    reader.align_to_byte();
+    let checksum = reader
+        .remaining_bytes()
+        .get(..4)
+        .ok_or("compressed data is truncated")?;
+    if u32::from_be_bytes(checksum.try_into().unwrap()) != adler32(&out) {
+        return Err("compressed data has a wrong checksum".to_string());
+    }
+
+    Ok(out)
+}
+
+fn inflate_stored(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), String> {
+    reader.align_to_byte();
+    let length = reader.read_bits(16)?;
+    let inverted_length = reader.read_bits(16)?;
+    if length != !inverted_length & 0xffff {
+        return Err("stored block has an invalid length".to_string());
+    }
+
+    let bytes = reader
+        .remaining_bytes()
+        .get(..length as usize)
+        .ok_or("compressed data is truncated")?;
+    out.extend_from_slice(bytes);
+    reader.skip_bytes(length as usize);
+    Ok(())
+}
+
+fn inflate_huffman(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    (literals, distances): &(Huffman, Huffman),
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length = LENGTH_BASE[index] as usize
+                    + reader.read_bits(LENGTH_EXTRA_BITS[index])? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err("compressed data has an invalid distance code".to_string());
+                }
+                let distance = DISTANCE_BASE[index] as usize
+                    + reader.read_bits(DISTANCE_EXTRA_BITS[index])? as usize;
+                if distance > out.len() {
+                    return Err("compressed data refers back before its start".to_string());
+                }
+
+                // The source and destination may overlap, which repeats the
+                // last 'distance' bytes
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => return Err("compressed data has an invalid length code".to_string()),
+        }
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    let literals = Huffman::new(&lengths).expect("the fixed codes are valid");
+    let distances = Huffman::new(&[5; 30]).expect("the fixed codes are valid");
+    (literals, distances)
+}
+
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literal_count = reader.read_bits(5)? as usize + 257;
+    let distance_count = reader.read_bits(5)? as usize + 1;
+    let code_length_count = reader.read_bits(4)? as usize + 4;
+
+    let mut code_length_lengths = [0; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_length_lengths[index] = reader.read_bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_length_lengths)?;
+
+    // The lengths of both alphabets are stored as one sequence, in which
+    // repetitions may cross from one into the other
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(reader)? {
+            length @ 0..=15 => (length as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or("compressed data repeats a missing code length")?;
+                (previous, 3 + reader.read_bits(2)?)
+            }
+            17 => (0, 3 + reader.read_bits(3)?),
+            _ => (0, 11 + reader.read_bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() != literal_count + distance_count {
+        return Err("compressed data has too many code lengths".to_string());
+    }
+    if lengths[256] == 0 {
+        return Err("compressed data has no end of block code".to_string());
+    }
+
+    let literals = Huffman::new(&lengths[..literal_count])?;
+    let distances = Huffman::new(&lengths[literal_count..])?;
+    Ok((literals, distances))
+}
+
+/// Canonical Huffman code, decoded one bit at a time by walking the codes of
+/// each length in order.
+struct Huffman {
+    /// Number of codes of every length
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    /// Symbols ordered by their code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject codes that use more bit patterns than exist
+        let mut available = 1i32;
+        for &count in &counts[1..] {
+            available = available * 2 - count as i32;
+            if available < 0 {
+                return Err("compressed data has an invalid Huffman code".to_string());
+            }
+        }
+
+        let mut offsets = [0; MAX_CODE_LENGTH + 2];
+        for length in 1..=MAX_CODE_LENGTH {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; offsets[MAX_CODE_LENGTH + 1] as usize];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                let offset = &mut offsets[length as usize];
+                symbols[*offset as usize] = symbol as u16;
+                *offset += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        // 'first' is the first code of the current length, 'index' the
+        // position of its symbol
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+        for &count in &self.counts[1..] {
+            code |= reader.read_bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("compressed data has an invalid Huffman code".to_string())
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    buffer: u32,
+    bit_count: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// Reads 'count' bits, at most 16, least significant bit first.
+    fn read_bits(&mut self, count: u8) -> Result<u32, String> {
+        while self.bit_count < count {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or("compressed data is truncated")?;
+            self.buffer |= (byte as u32) << self.bit_count;
+            self.position += 1;
+            self.bit_count += 8;
+        }
+
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    /// Drops the bits left in the current byte.
+    fn align_to_byte(&mut self) {
+        let extra = self.bit_count % 8;
+        self.buffer >>= extra;
+        self.bit_count -= extra;
+    }
+
+    /// The bytes after the ones already read, expects to be aligned.
+    fn remaining_bytes(&self) -> &'a [u8] {
+        let buffered = (self.bit_count / 8) as usize;
+        &self.bytes[self.position - buffered..]
+    }
+
+    fn skip_bytes(&mut self, count: usize) {
+        let buffered = (self.bit_count / 8) as usize;
+        self.position = self.position - buffered + count;
+        self.buffer = 0;
+        self.bit_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writing::zlib;
+
+    const TEXT: &[u8] = b"It was the best of times, it was the worst of times, it was the \
+        age of wisdom, it was the age of foolishness, it was the epoch of belief, it was the \
+        epoch of incredulity, it was the season of Light, it was the season of Darkness.";
+
+    /// TEXT compressed by zlib into a single block with dynamic Huffman codes.
+    const DYNAMIC: [u8; 107] = [
+        120, 218, 117, 205, 193, 9, 128, 48, 16, 68, 209, 86, 182, 0, 177, 10, 47, 130, 77, 36, 58,
+        49, 139, 49, 43, 217, 149, 96, 247, 146, 147, 8, 122, 126, 127, 152, 209, 168, 58, 37, 139,
+        32, 15, 53, 146, 64, 198, 59, 180, 35, 126, 164, 74, 249, 35, 183, 162, 65, 101, 93, 100,
+        255, 146, 32, 146, 88, 99, 134, 190, 135, 56, 100, 142, 45, 240, 72, 140, 240, 109, 156,
+        231, 130, 229, 76, 108, 215, 43, 80, 56, 149, 220, 138, 137, 215, 104, 63, 54, 184, 178,
+        181, 223, 254, 6, 239, 254, 80, 84,
+    ];
+
+    /// 'data' in a single stored block.
+    fn stored(data: &[u8]) -> Vec<u8> {
+        let length = data.len() as u16;
+        let mut bytes = vec![0x78, 0x01, 0x01];
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&(!length).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&adler32(data).to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn decompresses_stored_blocks() {
+        assert_eq!(decompress(&stored(b"hello")).unwrap(), b"hello");
+        assert_eq!(decompress(&stored(b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn decompresses_fixed_huffman_codes() {
+        let data: Vec<u8> = (0..5000u32).map(|i| (i * i % 7 + i / 100) as u8).collect();
+        assert_eq!(decompress(&zlib::compress(&data)).unwrap(), data);
+        assert_eq!(decompress(&zlib::compress(TEXT)).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn decompresses_dynamic_huffman_codes() {
+        assert_eq!(
+            (DYNAMIC[2] >> 1) & 0b11,
+            2,
+            "block doesn't use dynamic codes"
+        );
+        assert_eq!(decompress(&DYNAMIC).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert!(decompress(&[0x78]).is_err());
+        assert!(decompress(&[0x79, 0x01, 0x01, 0, 0, 0xff, 0xff, 0, 0, 0, 1]).is_err());
+        // Preset dictionary
+        assert!(decompress(&[0x78, 0xbb, 0x01, 0, 0, 0xff, 0xff, 0, 0, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_and_corrupt_data() {
+        for length in 2..DYNAMIC.len() {
+            assert!(decompress(&DYNAMIC[..length]).is_err(), "length {length}");
+        }
+
+        let mut wrong_checksum = DYNAMIC;
+        *wrong_checksum.last_mut().unwrap() ^= 1;
+        assert!(decompress(&wrong_checksum).is_err());
+
+        let mut wrong_length = stored(b"hello");
+        wrong_length[5] ^= 1;
+        assert!(decompress(&wrong_length).is_err());
+    }
+}
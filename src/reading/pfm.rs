@@ -0,0 +1,153 @@
+use super::{pixel_count, DecodedImage};
+
+use crate::Color;
+
+/// Decodes a color (PF) or grayscale (Pf) Portable FloatMap. A negative
+/// scale in the header marks the floats as little endian, a positive one as
+/// big endian.
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let channels = match bytes.get(..2) {
+        Some(b"PF") => 3,
+        Some(b"Pf") => 1,
+        _ => return Err("not a Portable FloatMap".to_string()),
+    };
+
+    // The header consists of three whitespace separated tokens after the
+    // magic number, and a single whitespace character ends it
+    let mut tokens = Vec::with_capacity(3);
+    let mut position = 2;
+    while tokens.len() < 3 {
+        while bytes.get(position).is_some_and(u8::is_ascii_whitespace) {
+            position += 1;
+        }
+        let start = position;
+        while bytes
+            .get(position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        if start == position {
+            return Err("header is truncated".to_string());
+        }
+        tokens.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+    }
+    position += 1;
+
+    let parse_size = |token: &str| token.parse::<u32>().ok().filter(|&n| n > 0);
+    let (Some(width), Some(height)) = (parse_size(&tokens[0]), parse_size(&tokens[1])) else {
+        return Err(format!(
+            "image has an invalid size of {}x{}",
+            tokens[0], tokens[1]
+        ));
+    };
+    let scale: f32 = tokens[2]
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid scale", tokens[2]))?;
+    let little_endian = scale < 0.0;
+
+    let float_count = pixel_count(width as usize, height as usize)? * channels;
+    let data = bytes
+        .get(position..)
+        .filter(|data| data.len() >= float_count * 4)
+        .ok_or_else(|| {
+            format!(
+                "pixel data is truncated, expected {} pixels",
+                width as usize * height as usize
+            )
+        })?;
+
+    let floats: Vec<f32> = data[..float_count * 4]
+        .chunks_exact(4)
+        .map(|chunk| {
+            let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            match little_endian {
+                true => f32::from_le_bytes(bytes),
+                false => f32::from_be_bytes(bytes),
+            }
+        })
+        .collect();
+
+    // The rows are stored from bottom to top
+    let pixels = floats
+        .chunks_exact(width as usize * channels)
+        .rev()
+        .flat_map(|row| row.chunks_exact(channels))
+        .map(|pixel| match pixel {
+            [r, g, b] => Color::new(*r, *g, *b),
+            _ => Color::new(pixel[0], pixel[0], pixel[0]),
+        })
+        .collect();
+
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels,
+        is_linear: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writing::pfm;
+
+    fn components(image: &DecodedImage) -> Vec<f32> {
+        image
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect()
+    }
+
+    #[test]
+    fn reads_what_the_writer_writes() {
+        let pixels: Vec<_> = (0..6)
+            .map(|i| Color::new(i as f32, -0.125 * i as f32, 1e6))
+            .collect();
+        let mut bytes = Vec::new();
+        pfm::write(&mut bytes, 3, 2, &pixels).unwrap();
+
+        let image = decode(&bytes).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert!(image.is_linear);
+        let expected: Vec<f32> = pixels.iter().flat_map(|c| [c.r, c.g, c.b]).collect();
+        assert_eq!(components(&image), expected);
+    }
+
+    #[test]
+    fn reads_big_endian_and_grayscale_files() {
+        let mut bytes = b"PF 1 1 1.0\n".to_vec();
+        for value in [1.0f32, 2.0, 3.0] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        assert_eq!(components(&decode(&bytes).unwrap()), [1.0, 2.0, 3.0]);
+
+        // Bottom row first
+        let mut bytes = b"Pf\n1 2\n-1\n".to_vec();
+        for value in [0.5f32, 4.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let image = decode(&bytes).unwrap();
+        assert_eq!(components(&image), [4.0, 4.0, 4.0, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert!(decode(b"P6 1 1 255\n").is_err());
+        assert!(decode(b"PF 1 1").is_err());
+        assert!(decode(b"PF 0 1 -1\n").is_err());
+        assert!(decode(b"PF 1 1 big\n\0\0\0\0\0\0\0\0\0\0\0\0").is_err());
+        assert!(decode(b"PF 4294967295 4294967295 -1\n\0")
+            .unwrap_err()
+            .contains("unsupported size"));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let mut bytes = Vec::new();
+        pfm::write(&mut bytes, 2, 2, &[Color::new(1.0, 2.0, 3.0); 4]).unwrap();
+        assert!(decode(&bytes).is_ok());
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use super::{inflate, pixel_count, DecodedImage};
+
+use crate::writing::png::{crc32, paeth, SIGNATURE};
+use crate::Color;
+
+/// Start and step of the columns and rows of the seven Adam7 passes.
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, String> {
+        let data: &[u8; 13] = data.try_into().map_err(|_| "IHDR chunk has a wrong size")?;
+        let width = u32::from_be_bytes(data[0..4].try_into().unwrap());
+        let height = u32::from_be_bytes(data[4..8].try_into().unwrap());
+        let [bit_depth, color_type, compression, filter, interlace] = data[8..13] else {
+            unreachable!()
+        };
+
+        if width == 0 || height == 0 {
+            return Err(format!("image has an invalid size of {width}x{height}"));
+        }
+        let valid_depths: &[u8] = match color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(format!("unknown color type {color_type}")),
+        };
+        if !valid_depths.contains(&bit_depth) {
+            return Err(format!(
+                "bit depth {bit_depth} isn't allowed for color type {color_type}"
+            ));
+        }
+        if compression != 0 || filter != 0 || interlace > 1 {
+            return Err("unknown compression, filter or interlace method".to_string());
+        }
+
+        Ok(Self {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            interlaced: interlace == 1,
+        })
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    /// Bytes per row of 'width' pixels, without the filter type.
+    fn row_length(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+}
+
+/// Decodes a PNG of any standard bit depth and color type, interlaced or
+/// not. Alpha is ignored.
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let mut position = SIGNATURE.len();
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+
+    loop {
+        let (kind, data) = read_chunk(bytes, &mut position)?;
+        if header.is_none() && &kind != b"IHDR" {
+            return Err("file doesn't start with an IHDR chunk".to_string());
+        }
+
+        match &kind {
+            b"IHDR" => header = Some(Header::parse(data)?),
+            b"PLTE" => {
+                if !data.len().is_multiple_of(3) {
+                    return Err("PLTE chunk has a wrong size".to_string());
+                }
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| {
+                        Color::new(
+                            rgb[0] as f32 / 255.0,
+                            rgb[1] as f32 / 255.0,
+                            rgb[2] as f32 / 255.0,
+                        )
+                    })
+                    .collect();
+            }
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            // Ancillary chunks have a lowercase first letter and can be skipped
+            _ if kind[0].is_ascii_lowercase() => {}
+            _ => {
+                return Err(format!(
+                    "unknown critical chunk '{}'",
+                    String::from_utf8_lossy(&kind)
+                ))
+            }
+        }
+    }
+
+    let header = header.expect("the first chunk is IHDR");
+    if header.color_type == 3 && palette.is_empty() {
+        return Err("indexed image has no PLTE chunk".to_string());
+    }
+
+    let data = inflate::decompress(&compressed).map_err(|message| format!("IDAT: {message}"))?;
+    let pixels = decode_pixels(&header, &palette, &data)?;
+
+    Ok(DecodedImage {
+        width: header.width,
+        height: header.height,
+        pixels,
+        is_linear: false,
+    })
+}
+
+fn read_chunk<'a>(bytes: &'a [u8], position: &mut usize) -> Result<([u8; 4], &'a [u8]), String> {
+    let truncated = || "file is truncated, it has no IEND chunk".to_string();
+
+    let start = *position;
+    let length_bytes = bytes.get(start..start + 4).ok_or_else(truncated)?;
+    let length = u32::from_be_bytes(length_bytes.try_into().unwrap()) as usize;
+    let chunk = bytes
+        .get(start + 4..start + 12 + length)
+        .ok_or_else(truncated)?;
+
+    let (kind_and_data, crc) = chunk.split_at(4 + length);
+    let kind: [u8; 4] = kind_and_data[..4].try_into().unwrap();
+    if crc32(kind_and_data.iter()) != u32::from_be_bytes(crc.try_into().unwrap()) {
+        return Err(format!(
+            "chunk '{}' has a wrong checksum",
+            String::from_utf8_lossy(&kind)
+        ));
+    }
+
+    *position = start + 12 + length;
+    Ok((kind, &kind_and_data[4..]))
+}
+
+fn decode_pixels(header: &Header, palette: &[Color], data: &[u8]) -> Result<Vec<Color>, String> {
+    let width = header.width as usize;
+    let height = header.height as usize;
+    let pixel_count = pixel_count(width, height)?;
+
+    let passes: &[_] = match header.interlaced {
+        true => &ADAM7_PASSES,
+        false => &[(0, 0, 1, 1)],
+    };
+    // Width and bytes of each pass, every row starting with its filter
+    // type. Empty passes don't even store that.
+    let pass_sizes: Vec<_> = passes
+        .iter()
+        .map(|&(x_start, y_start, x_step, y_step)| {
+            let pass_width = width.saturating_sub(x_start).div_ceil(x_step);
+            let pass_height = height.saturating_sub(y_start).div_ceil(y_step);
+            let size = match pass_width == 0 {
+                true => 0,
+                false => (header.row_length(pass_width) + 1) * pass_height,
+            };
+            (pass_width, size)
+        })
+        .collect();
+    if pass_sizes.iter().map(|&(_, size)| size).sum::<usize>() > data.len() {
+        return Err("image data is shorter than the image size requires".to_string());
+    }
+
+    let mut pixels = vec![Color::new(0.0, 0.0, 0.0); pixel_count];
+    let mut offset = 0;
+    for (&(x_start, y_start, x_step, y_step), &(pass_width, size)) in passes.iter().zip(&pass_sizes)
+    {
+        if size == 0 {
+            continue;
+        }
+
+        let row_length = header.row_length(pass_width);
+        let filtered = &data[offset..offset + size];
+        offset += size;
+
+        let rows = unfilter(filtered, row_length, header.bits_per_pixel().div_ceil(8))?;
+        for (pass_y, row) in rows.chunks_exact(row_length).enumerate() {
+            let y = y_start + pass_y * y_step;
+            for pass_x in 0..pass_width {
+                let x = x_start + pass_x * x_step;
+                pixels[y * width + x] = pixel(header, palette, row, pass_x)?;
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Undoes the per row filters, returning the rows without filter types.
+fn unfilter(data: &[u8], row_length: usize, bytes_per_pixel: usize) -> Result<Vec<u8>, String> {
+    let row_count = data.len() / (row_length + 1);
+    let mut rows = vec![0; row_count * row_length];
+    let empty_row = vec![0; row_length];
+
+    for (y, filtered) in data.chunks_exact(row_length + 1).enumerate() {
+        let (done, current) = rows.split_at_mut(y * row_length);
+        let previous = match y {
+            0 => &empty_row[..],
+            _ => &done[(y - 1) * row_length..],
+        };
+        let current = &mut current[..row_length];
+        let (filter, filtered) = (filtered[0], &filtered[1..]);
+
+        for i in 0..row_length {
+            let left = if i >= bytes_per_pixel {
+                current[i - bytes_per_pixel]
+            } else {
+                0
+            };
+            let up = previous[i];
+            let up_left = if i >= bytes_per_pixel {
+                previous[i - bytes_per_pixel]
+            } else {
+                0
+            };
+
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(format!("row {y} has an unknown filter type {filter}")),
+            };
+            current[i] = filtered[i].wrapping_add(predictor);
+        }
+    }
+
+    Ok(rows)
+}
+
+fn pixel(header: &Header, palette: &[Color], row: &[u8], x: usize) -> Result<Color, String> {
+    let depth = header.bit_depth as usize;
+    let channels = header.channels();
+    let max_value = ((1u32 << depth) - 1) as f32;
+
+    let sample = |channel: usize| -> u32 {
+        let bit = (x * channels + channel) * depth;
+        match depth {
+            16 => u16::from_be_bytes([row[bit / 8], row[bit / 8 + 1]]) as u32,
+            8 => row[bit / 8] as u32,
+            // Smaller samples are packed starting at the most significant bit
+            _ => (row[bit / 8] as u32 >> (8 - depth - bit % 8)) & ((1 << depth) - 1),
+        }
+    };
+
+    let color = match header.color_type {
+        0 | 4 => {
+            let gray = sample(0) as f32 / max_value;
+            Color::new(gray, gray, gray)
+        }
+        3 => {
+            let index = sample(0) as usize;
+            *palette
+                .get(index)
+                .ok_or_else(|| format!("palette index {index} is out of range"))?
+        }
+        _ => Color::new(
+            sample(0) as f32 / max_value,
+            sample(1) as f32 / max_value,
+            sample(2) as f32 / max_value,
+        ),
+    };
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writing::{png, zlib};
+
+    fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = (data.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&crc32(kind.iter().chain(data)).to_be_bytes());
+        bytes
+    }
+
+    /// A PNG with the given header fields and already filtered image data.
+    fn file(
+        width: u32,
+        height: u32,
+        depth_and_type: [u8; 2],
+        interlace: u8,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[depth_and_type[0], depth_and_type[1], 0, 0, interlace]);
+
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend(chunk(b"IHDR", &header));
+        bytes.extend(chunk(b"IDAT", &zlib::compress(data)));
+        bytes.extend(chunk(b"IEND", &[]));
+        bytes
+    }
+
+    fn gray(pixels: &[Color]) -> Vec<u8> {
+        pixels
+            .iter()
+            .map(|color| (color.g * 255.0).round() as u8)
+            .collect()
+    }
+
+    #[test]
+    fn reads_what_the_writer_writes() {
+        let (width, height) = (13, 7);
+        let bytes: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i * 37 % 256) ^ (i / 5)) as u8)
+            .collect();
+        let mut encoded = Vec::new();
+        png::write(&mut encoded, width, height, 3, &bytes).unwrap();
+
+        let image = decode(&encoded).unwrap();
+        assert_eq!((image.width, image.height), (width, height));
+        assert!(!image.is_linear);
+        let decoded: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .map(|component| (component * 255.0).round() as u8)
+            .collect();
+        assert_eq!(decoded, bytes);
+    }
+
+    #[test]
+    fn undoes_every_filter() {
+        // Sub, then Paeth
+        let image = decode(&file(2, 2, [8, 0], 0, &[1, 10, 10, 4, 20, 20])).unwrap();
+        assert_eq!(gray(&image.pixels), [10, 20, 30, 50]);
+        // Average, then Up
+        let image = decode(&file(2, 2, [8, 0], 0, &[3, 10, 15, 2, 20, 30])).unwrap();
+        assert_eq!(gray(&image.pixels), [10, 20, 30, 50]);
+    }
+
+    #[test]
+    fn reads_palettes_and_16_bit_samples() {
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend(chunk(b"IHDR", &[0, 0, 0, 3, 0, 0, 0, 1, 1, 3, 0, 0, 0]));
+        bytes.extend(chunk(b"PLTE", &[255, 0, 0, 0, 0, 255]));
+        bytes.extend(chunk(b"IDAT", &zlib::compress(&[0, 0b0100_0000])));
+        bytes.extend(chunk(b"IEND", &[]));
+        let image = decode(&bytes).unwrap();
+        let blues: Vec<_> = image.pixels.iter().map(|color| color.b).collect();
+        assert_eq!(blues, [0.0, 1.0, 0.0]);
+
+        let image = decode(&file(1, 1, [16, 0], 0, &[0, 0x80, 0x00])).unwrap();
+        assert!((image.pixels[0].g - 0x8000 as f32 / 65535.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reads_interlaced_images() {
+        // The Adam7 passes of a 3x3 image hold the pixels (0, 0), (2, 0),
+        // (0, 2) and (2, 2), (1, 0) and (1, 2), and the middle row
+        let data = [0, 0, 0, 2, 0, 20, 22, 0, 1, 0, 21, 0, 10, 11, 12];
+        let image = decode(&file(3, 3, [8, 0], 1, &data)).unwrap();
+        assert_eq!(gray(&image.pixels), [0, 1, 2, 10, 11, 12, 20, 21, 22]);
+    }
+
+    #[test]
+    fn rejects_oversized_images() {
+        let bytes = file(0x7fff_ffff, 0x7fff_ffff, [8, 2], 0, &[0; 4]);
+        assert!(decode(&bytes).unwrap_err().contains("unsupported size"));
+
+        let bytes = file(100, 100, [8, 2], 0, &[0; 301]);
+        assert!(decode(&bytes).unwrap_err().contains("shorter"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_corrupt_files() {
+        let bytes = file(2, 2, [8, 0], 0, &[0, 1, 2, 0, 3, 4]);
+        for length in SIGNATURE.len()..bytes.len() {
+            assert!(decode(&bytes[..length]).is_err(), "length {length}");
+        }
+
+        let mut wrong_checksum = bytes.clone();
+        wrong_checksum[SIGNATURE.len() + 10] ^= 1;
+        assert!(decode(&wrong_checksum).unwrap_err().contains("checksum"));
+
+        assert!(decode(&file(2, 2, [3, 2], 0, &[])).is_err());
+        assert!(decode(&file(0, 2, [8, 0], 0, &[])).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use super::{pixel_count, DecodedImage};
+
+use crate::Color;
+
+/// Decodes a plain (P3) or binary (P6) PPM file with up to 16 bits per
+/// component.
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let mut reader = Reader { bytes, position: 2 };
+    let is_binary = bytes.starts_with(b"P6");
+
+    let width = reader.header_number("width")?;
+    let height = reader.header_number("height")?;
+    let max_value = reader.header_number("maximum value")?;
+    if width == 0 || height == 0 {
+        return Err(format!("image has an invalid size of {width}x{height}"));
+    }
+    if max_value == 0 || max_value > u16::MAX as u32 {
+        return Err(format!(
+            "maximum value has to be between 1 and 65535, got {max_value}"
+        ));
+    }
+
+    let sample_count = pixel_count(width as usize, height as usize)? * 3;
+    let samples = match is_binary {
+        true => reader.binary_samples(sample_count, max_value)?,
+        false => reader.plain_samples(sample_count, max_value)?,
+    };
+
+    let scale = 1.0 / max_value as f32;
+    let pixels = samples
+        .chunks_exact(3)
+        .map(|rgb| {
+            Color::new(
+                rgb[0] as f32 * scale,
+                rgb[1] as f32 * scale,
+                rgb[2] as f32 * scale,
+            )
+        })
+        .collect();
+
+    Ok(DecodedImage {
+        width,
+        height,
+        pixels,
+        is_linear: false,
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    /// Skips whitespace and comments, which run from '#' to the end of the
+    /// line.
+    fn skip_whitespace(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.position) {
+            match byte {
+                b'#' => {
+                    while self.bytes.get(self.position).is_some_and(|&b| b != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                _ if byte.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        self.skip_whitespace();
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_digit)
+        {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn header_number(&mut self, name: &str) -> Result<u32, String> {
+        self.number()
+            .ok_or_else(|| format!("header is missing the {name}"))
+    }
+
+    fn plain_samples(&mut self, count: usize, max_value: u32) -> Result<Vec<u16>, String> {
+        // Every sample takes at least a byte, which bounds what a wrong
+        // header can make this allocate
+        let mut samples = Vec::with_capacity(count.min(self.bytes.len()));
+        for _ in 0..count {
+            let sample = self.number().ok_or_else(|| {
+                format!(
+                    "expected {} pixels, but the data ends after {}",
+                    count / 3,
+                    samples.len() / 3
+                )
+            })?;
+            if sample > max_value {
+                return Err(format!(
+                    "value {sample} is larger than the maximum value {max_value}"
+                ));
+            }
+            samples.push(sample as u16);
+        }
+        Ok(samples)
+    }
+
+    fn binary_samples(&mut self, count: usize, max_value: u32) -> Result<Vec<u16>, String> {
+        // Exactly one whitespace character separates the header from the data
+        self.position += 1;
+
+        let bytes_per_sample = if max_value < 256 { 1 } else { 2 };
+        let data = self
+            .bytes
+            .get(self.position..)
+            .filter(|data| data.len() >= count * bytes_per_sample)
+            .ok_or_else(|| format!("pixel data is truncated, expected {} pixels", count / 3))?;
+
+        let samples = match bytes_per_sample {
+            1 => data[..count].iter().map(|&byte| byte as u16).collect(),
+            _ => data[..count * 2]
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect(),
+        };
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components(image: &DecodedImage) -> Vec<f32> {
+        image
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect()
+    }
+
+    #[test]
+    fn reads_plain_files() {
+        let image = decode(b"P3\n# a comment\n2 1 # size\n4\n0 1 2\n3 4 4\n").unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(components(&image), [0.0, 0.25, 0.5, 0.75, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn reads_binary_files() {
+        let image = decode(b"P6 1 2 3\n\x00\x01\x03\x03\x00\x00").unwrap();
+        assert_eq!(components(&image), [0.0, 1.0 / 3.0, 1.0, 1.0, 0.0, 0.0]);
+
+        let image = decode(b"P6 1 1 65535\n\xff\xff\x80\x00\x00\x00").unwrap();
+        assert_eq!(components(&image)[0], 1.0);
+        assert!((components(&image)[1] - 0x8000 as f32 / 65535.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert!(decode(b"P3 0 1 255\n").is_err());
+        assert!(decode(b"P3 1 1 0\n0 0 0").is_err());
+        assert!(decode(b"P3 1 1 70000\n0 0 0").is_err());
+        assert!(decode(b"P3 1 1\n").is_err());
+        assert!(decode(b"P6 4294967295 4294967295 255\n\x00")
+            .unwrap_err()
+            .contains("unsupported size"));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let plain = b"P3 2 1 255\n0 1 2 3 4 5";
+        assert!(decode(&plain[..plain.len() - 2]).is_err());
+        assert!(decode(b"P3 1 1 3\n0 1 4").is_err());
+
+        let binary = b"P6 2 1 255\n\x00\x01\x02\x03\x04\x05";
+        assert!(decode(binary).is_ok());
+        assert!(decode(&binary[..binary.len() - 1]).is_err());
+    }
+}
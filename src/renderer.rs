@@ -1,33 +1,44 @@
+use crate::background::Gradient;
 use crate::camera::Camera;
 use crate::hittable_list::HittableList;
-use crate::writing::FileWriter;
-use crate::{Color, Hittable, Interval, Ray};
+use crate::random;
+use crate::writing::{FileWriter, ImageFormat};
+use crate::{
+    Color, HitRecord, Hittable, Interval, Lobe, Point3, Ray, SharedBackground, SharedHittable,
+    SharedMaterial, Vec3,
+};
 
 use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 
+/// Side length in pixels of the square tiles the image gets split into.
+const TILE_SIZE: u32 = 16;
+
 pub struct ImageBuffer {
+    width: usize,
     buffer: Vec<Color>,
 }
 
 impl ImageBuffer {
     fn new(image_width: usize, image_height: usize) -> Self {
-        let capacity = image_width * image_height;
-        let buffer = Vec::with_capacity(capacity);
-        Self { buffer }
-    }
-
-    /// Merge multiple other ImageBuffer struct into self
-    fn merge(&mut self, others: Vec<ImageBuffer>) {
-        for image_buf in others.iter() {
-            for (i, color) in image_buf.buffer.iter().enumerate() {
-                self.buffer[i] += *color;
-            }
+        let buffer = vec![Color::new(0.0, 0.0, 0.0); image_width * image_height];
+        Self {
+            width: image_width,
+            buffer,
         }
     }
 
-    fn write_pixel(&mut self, color: Color) {
-        self.buffer.push(color);
+    /// Copies the row-major pixels of a rendered tile into the image.
+    fn write_tile(&mut self, tile: &Tile, pixels: &[Color]) {
+        let tile_width = tile.width() as usize;
+        for (i, row) in (tile.rows.0..tile.rows.1).enumerate() {
+            let start = row as usize * self.width + tile.columns.0 as usize;
+            let tile_row = &pixels[i * tile_width..(i + 1) * tile_width];
+            self.buffer[start..start + tile_width].copy_from_slice(tile_row);
+        }
     }
 
     pub fn get_buffer(&self) -> &Vec<Color> {
@@ -35,16 +46,64 @@ impl ImageBuffer {
     }
 }
 
+/// Rectangular part of the image, given as half-open row and column ranges.
+struct Tile {
+    rows: (u32, u32),
+    columns: (u32, u32),
+}
+
+impl Tile {
+    fn width(&self) -> u32 {
+        self.columns.1 - self.columns.0
+    }
+}
+
+/// Queue of tiles shared by all render threads. Every thread keeps taking the
+/// next unrendered tile until none are left, so threads that happen to get
+/// cheap regions of the image simply render more tiles.
+struct TileQueue {
+    tiles: Vec<Tile>,
+    next: AtomicUsize,
+}
+
+impl TileQueue {
+    fn new(image_width: u32, image_height: u32) -> Self {
+        let mut tiles = vec![];
+        for row in (0..image_height).step_by(TILE_SIZE as usize) {
+            for column in (0..image_width).step_by(TILE_SIZE as usize) {
+                tiles.push(Tile {
+                    rows: (row, (row + TILE_SIZE).min(image_height)),
+                    columns: (column, (column + TILE_SIZE).min(image_width)),
+                });
+            }
+        }
+
+        Self {
+            tiles,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn pop(&self) -> Option<&Tile> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.tiles.get(index)
+    }
+}
+
+#[derive(Clone)]
 pub struct Renderer {
-    world: HittableList,
+    world: SharedHittable,
     camera: Camera,
     samples_per_pixel: u32,
     max_ray_depth: u32,
+    background: SharedBackground,
+    /// Emissive objects that get sampled directly at every diffuse bounce
+    lights: HittableList,
 }
 
 impl Renderer {
     pub fn new(
-        world: HittableList,
+        world: SharedHittable,
         camera: Camera,
         samples_per_pixel: u32,
         max_ray_depth: u32,
@@ -54,67 +113,82 @@ impl Renderer {
             camera,
             samples_per_pixel,
             max_ray_depth,
+            background: Arc::new(Gradient),
+            lights: HittableList::default(),
         }
     }
 
-    pub fn start(&self, threads: u32) -> io::Result<()> {
-        let thread_samples = self.calculate_sample_distribution(threads);
-        let (main_thread_samples, thread_samples) = thread_samples.split_first().unwrap();
+    /// Sets what rays that don't hit anything return, the default is a sky
+    /// gradient. Backgrounds that can be importance sampled get sampled
+    /// along with the lights.
+    pub fn with_background(mut self, background: SharedBackground) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Registers the objects that light the scene, they have to be part of
+    /// the world as well. At every diffuse bounce, one of them gets sampled
+    /// with a shadow ray, which makes small lights far less noisy. Without
+    /// lights, rays only find light by bouncing into it.
+    pub fn with_lights(mut self, lights: HittableList) -> Self {
+        self.lights = lights;
+        self
+    }
 
-        let threads_left = threads - 1; // because the main thread will also do rendering
-        let thread_handles = self.spawn_render_threads(threads_left, thread_samples);
-        let main_buffer = Renderer::render(
-            &self.world,
-            &self.camera,
-            *main_thread_samples,
-            self.max_ray_depth,
-        );
-        let main_buffer = self.merge_thread_buffers(main_buffer, thread_handles);
+    /// Renders the image and writes it to 'path', or to stdout if no path is
+    /// given.
+    pub fn start(&self, threads: u32, format: ImageFormat, path: Option<&Path>) -> io::Result<()> {
+        let image = self.render_image(threads);
 
-        let mut file_writer =
-            FileWriter::new(self.camera.image_width(), self.camera.image_height())?;
-        file_writer.write_image(main_buffer, self.samples_per_pixel)?;
+        let mut file_writer = FileWriter::new(
+            format,
+            path,
+            self.camera.image_width(),
+            self.camera.image_height(),
+        )?;
+        file_writer.write_image(image, self.samples_per_pixel)?;
 
         Ok(())
     }
 
-    fn calculate_sample_distribution(&self, threads: u32) -> Vec<u32> {
-        let samples_per_pixel_per_thread = self.samples_per_pixel / threads;
-        let samples_left = self.samples_per_pixel % threads;
+    fn render_image(&self, threads: u32) -> ImageBuffer {
+        let image_width = self.camera.image_width();
+        let image_height = self.camera.image_height();
+
+        let queue = Arc::new(TileQueue::new(image_width, image_height));
+        let image = Arc::new(Mutex::new(ImageBuffer::new(
+            image_width as usize,
+            image_height as usize,
+        )));
 
-        let mut thread_samples = vec![samples_per_pixel_per_thread; threads as usize];
+        let threads_left = threads.saturating_sub(1); // because the main thread will also do rendering
+        let thread_handles = self.spawn_render_threads(threads_left, &queue, &image);
+        self.render_tiles(&queue, &image);
 
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..(samples_left as usize) {
-            thread_samples[i] += 1;
+        for handle in thread_handles {
+            handle.join().unwrap();
         }
 
-        thread_samples
+        // All threads have been joined, so this is the last reference
+        let image = Arc::try_unwrap(image).ok().unwrap();
+        image.into_inner().unwrap()
     }
 
     fn spawn_render_threads(
         &self,
         threads: u32,
-        thread_samples: &[u32],
-    ) -> Vec<JoinHandle<ImageBuffer>> {
+        queue: &Arc<TileQueue>,
+        image: &Arc<Mutex<ImageBuffer>>,
+    ) -> Vec<JoinHandle<()>> {
         let mut thread_handles = vec![];
 
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..(threads as usize) {
-            let world_clone = self.world.clone();
-            let camera_clone = self.camera.clone();
-            let max_ray_depth = self.max_ray_depth;
+        for _ in 0..threads {
+            // Cloning is cheap, the world is shared
+            let renderer = self.clone();
+            let queue = Arc::clone(queue);
+            let image = Arc::clone(image);
 
-            let samples_per_pixel = thread_samples[i];
-
-            let handle = thread::spawn(move || {
-                Renderer::render(
-                    &world_clone,
-                    &camera_clone,
-                    samples_per_pixel,
-                    max_ray_depth,
-                )
-            });
+            let handle = thread::spawn(move || renderer.render_tiles(&queue, &image));
 
             thread_handles.push(handle);
         }
@@ -122,72 +196,149 @@ impl Renderer {
         thread_handles
     }
 
-    fn merge_thread_buffers(
-        &self,
-        mut main_buffer: ImageBuffer,
-        thread_handles: Vec<JoinHandle<ImageBuffer>>,
-    ) -> ImageBuffer {
-        let mut buffers = vec![];
-        for handle in thread_handles.into_iter() {
-            let returned_buffer = handle.join().unwrap();
-            buffers.push(returned_buffer);
-        }
+    fn render_tiles(&self, queue: &TileQueue, image: &Mutex<ImageBuffer>) {
+        let mut pixels = Vec::with_capacity((TILE_SIZE * TILE_SIZE) as usize);
 
-        main_buffer.merge(buffers);
+        while let Some(tile) = queue.pop() {
+            pixels.clear();
 
-        main_buffer
-    }
+            for row in tile.rows.0..tile.rows.1 {
+                for col in tile.columns.0..tile.columns.1 {
+                    // Stream 0 is left to the scene setup
+                    let pixel_index = row as u64 * self.camera.image_width() as u64 + col as u64;
+                    random::reseed(pixel_index + 1);
 
-    fn render(
-        world: &HittableList,
-        camera: &Camera,
-        samples_per_pixel: u32,
-        max_ray_depth: u32,
-    ) -> ImageBuffer {
-        let mut buffer = ImageBuffer::new(
-            camera.image_width() as usize,
-            camera.image_height() as usize,
-        );
-
-        for row in 0..camera.image_height() {
-            for col in 0..camera.image_width() {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-
-                for _sample in 1..=samples_per_pixel {
-                    let ray = camera.get_ray(row, col);
-                    pixel_color += Renderer::ray_color(&ray, max_ray_depth, world);
+                    let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+
+                    for _sample in 1..=self.samples_per_pixel {
+                        let ray = self.camera.get_ray(row, col);
+                        pixel_color += self.ray_color(&ray, self.max_ray_depth, None);
+                    }
+
+                    pixels.push(pixel_color);
                 }
+            }
 
-                buffer.write_pixel(pixel_color);
+            image.lock().unwrap().write_tile(tile, &pixels);
+        }
+    }
+
+    /// 'scattering_pdf' is the density with which the previous hit scattered
+    /// 'ray', if it could have sampled the same direction towards a light.
+    fn ray_color(&self, ray: &Ray, depth: u32, scattering_pdf: Option<f32>) -> Color {
+        if depth == 0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let Some(hit_rec) = self.world.hit(ray, Interval::new(0.001, f32::INFINITY)) else {
+            let color = self.background.color(ray);
+            return match scattering_pdf {
+                Some(scattering_pdf) if self.background.is_importance_sampled() => {
+                    let light_pdf = self.light_pdf(ray.origin(), ray.direction());
+                    color * power_heuristic(scattering_pdf, light_pdf)
+                }
+                _ => color,
+            };
+        };
+
+        // At this point, the hit record should have a material, so we can unwrap
+        let material = hit_rec.material.as_ref().unwrap();
+        let mut emitted = material.emitted(&hit_rec);
+
+        // Light sampling at the previous hit accounted for part of this
+        // light already
+        if let Some(scattering_pdf) = scattering_pdf {
+            if !emitted.is_black() {
+                let light_pdf = self.light_pdf(ray.origin(), ray.direction());
+                emitted *= power_heuristic(scattering_pdf, light_pdf);
             }
         }
 
-        buffer
+        let Some(scatter) = material.scatter(ray, &hit_rec) else {
+            return emitted;
+        };
+
+        match scatter.lobe {
+            Lobe::Diffuse { pdf } if self.has_lights() => {
+                let direct = self.sample_light(ray, &hit_rec, material);
+                let incoming = self.ray_color(&scatter.ray, depth - 1, Some(pdf));
+                emitted + direct + scatter.attenuation * incoming
+            }
+            _ => {
+                let incoming = self.ray_color(&scatter.ray, depth - 1, None);
+                emitted + scatter.attenuation * incoming
+            }
+        }
     }
 
-    fn ray_color(ray: &Ray, depth: u32, world: &impl Hittable) -> Color {
-        if depth == 0 {
+    /// Light scattered along 'ray' after arriving from a random direction
+    /// towards one of the lights. Whatever the shadow ray hits first is what
+    /// it sees, so objects in between block the light, while volumes it
+    /// passes through dim it.
+    fn sample_light(&self, ray: &Ray, hit_rec: &HitRecord, material: &SharedMaterial) -> Color {
+        let direction = self.random_light_direction(hit_rec.point);
+        let light_pdf = self.light_pdf(hit_rec.point, direction);
+        let scattered = material.evaluate(ray, hit_rec, direction);
+        if light_pdf <= 0.0 || scattered.is_black() {
             return Color::new(0.0, 0.0, 0.0);
         }
 
-        if let Some(hit_rec) = world.hit(ray, Interval::new(0.001, f32::INFINITY)) {
-            // At this point, the hit record should have a material, so we can unwrap
-            let material = hit_rec.material.as_ref().unwrap();
+        let shadow_ray = Ray::with_time(hit_rec.point, direction, ray.time());
+        let (emitted, end) = match self
+            .world
+            .shadow_hit(&shadow_ray, Interval::new(0.001, f32::INFINITY))
+        {
+            Some(light_hit) => (
+                light_hit.material.as_ref().unwrap().emitted(&light_hit),
+                light_hit.t,
+            ),
+            None => (self.background.color(&shadow_ray), f32::INFINITY),
+        };
+        if emitted.is_black() {
+            return emitted;
+        }
+        let transmittance = self
+            .world
+            .transmittance(&shadow_ray, Interval::new(0.001, end));
 
-            let color = if let Some(scatter) = material.scatter(ray, &hit_rec) {
-                scatter.attenuation * Self::ray_color(&scatter.ray, depth - 1, world)
-            } else {
-                Color::new(0.0, 0.0, 0.0)
-            };
+        let scattering_pdf = material.scattering_pdf(ray, hit_rec, direction);
+        let weight = power_heuristic(light_pdf, scattering_pdf);
+        scattered * emitted * (transmittance * weight / light_pdf)
+    }
+
+    fn has_lights(&self) -> bool {
+        !self.lights.is_empty() || self.background.is_importance_sampled()
+    }
 
-            return color;
+    /// Picks the lights or the background with equal probability if both
+    /// can be sampled.
+    fn random_light_direction(&self, origin: Point3) -> Vec3 {
+        let sample_background = self.background.is_importance_sampled()
+            && (self.lights.is_empty() || random::random() < 0.5);
+        match sample_background {
+            true => self.background.random_direction(),
+            false => self.lights.random_direction(origin),
         }
+    }
 
-        // Gradient background
-        let white = Color::new(1.0, 1.0, 1.0);
-        let blue = Color::new(0.5, 0.7, 1.0);
-        let direction = ray.direction().normalized();
-        let lerp_factor = 0.5 * (direction.y + 1.0);
-        (1.0 - lerp_factor) * white + lerp_factor * blue
+    /// Density with which 'random_light_direction' picks 'direction'.
+    fn light_pdf(&self, origin: Point3, direction: Vec3) -> f32 {
+        let background_pdf = || self.background.pdf_value(direction);
+        match (
+            self.lights.is_empty(),
+            self.background.is_importance_sampled(),
+        ) {
+            (false, true) => 0.5 * (self.lights.pdf_value(origin, direction) + background_pdf()),
+            (false, false) => self.lights.pdf_value(origin, direction),
+            (true, true) => background_pdf(),
+            (true, false) => 0.0,
+        }
     }
 }
+
+/// Multiple importance sampling weight for a direction sampled with density
+/// 'pdf', that another strategy could have sampled with 'other_pdf'.
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (pdf, other_pdf) = (pdf * pdf, other_pdf * other_pdf);
+    pdf / (pdf + other_pdf)
+}
@@ -0,0 +1,1210 @@
+mod parser;
+
+use parser::{Entry, Section, Value};
+
+use crate::background::{self, EnvironmentMap, Gradient, Sky, Solid};
+use crate::bvh::BvhNode;
+use crate::camera::CameraBuilder;
+use crate::constant_medium::ConstantMedium;
+use crate::cuboid::Cuboid;
+use crate::disk::Disk;
+use crate::grid_medium::GridMedium;
+use crate::hittable_list::HittableList;
+use crate::instance::Instance;
+use crate::moving_sphere::MovingSphere;
+use crate::obj::ObjModel;
+use crate::plane::Plane;
+use crate::quad::Quad;
+use crate::reading;
+use crate::sphere::Sphere;
+use crate::texture::{
+    Checker, Filter, ImageTexture, Marble, SolidColor, Turbulence, UvChecker, Wood, Worley, Wrap,
+};
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+use crate::voxel_grid::VoxelGrid;
+use crate::{
+    Color, Conductor, Dialectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Metal,
+    Point3, Principled, Renderer, RoughConductor, RoughDielectric, SharedBackground,
+    SharedHittable, SharedMaterial, SharedTexture, Vec3,
+};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const DEFAULT_SAMPLES_PER_PIXEL: u32 = 100;
+pub const DEFAULT_MAX_RAY_DEPTH: u32 = 50;
+
+const TRANSFORM_KEYS: [&str; 3] = ["scale", "rotate", "translate"];
+
+/// Everything needed to render an image, as described by a scene file.
+///
+/// Scene files use a subset of TOML:
+///
+/// ```toml
+/// [render]
+/// samples_per_pixel = 100
+/// max_depth = 50
+/// background = "gradient"
+///
+/// [camera]
+/// image_width = 400
+/// aspect_ratio = 1.7778
+/// position = [13, 2, 3]
+/// look_at = [0, 0, 0]
+///
+/// [textures.tiles]
+/// type = "checker"
+/// scale = 0.5
+/// even = [0.2, 0.3, 0.1]
+/// odd = [0.9, 0.9, 0.9]
+///
+/// [materials.ground]
+/// type = "lambertian"
+/// albedo = "tiles"
+///
+/// [[objects]]
+/// type = "sphere"
+/// center = [0, -1000, 0]
+/// radius = 1000
+/// material = "ground"
+/// ```
+///
+/// The camera accepts all fields of CameraBuilder, missing ones take their
+/// default value. The aspect_ratio has to be larger than 0 and the
+/// vertical_fov between 0 and 180 degrees. The background is either "gradient" or a color.
+/// Alternatively, a [background] table with a type of "gradient", "solid"
+/// (color) or "environment_map" sets it. Environment maps take the path to an
+/// equirectangular HDR, PFM, PNG or PPM image, a rotation in degrees around
+/// the y axis and an intensity, and light the scene like a light source. The
+/// "sky" is a physically based daylight sky, set by a sun_direction, a
+/// turbidity between 1.7 (clear) and 10 (hazy), the ground_albedo below the
+/// horizon, the sun_size in degrees (0 for no sun disk) and an intensity.
+///
+/// Textures are "solid" (color), "checker" (scale, even, odd) with cubes in
+/// world space, "uv_checker" (columns, rows, even, odd) in texture space,
+/// where even and odd are colors or other textures, and "image" (path to a
+/// PPM, PNG, HDR or PFM file, filter "bilinear" or "nearest", wrap "repeat"
+/// or "clamp", color_space "srgb" or "linear"). The procedural textures
+/// "turbulence" (low, high), "marble" (base, veins), "wood" (light, dark) and
+/// "worley" (near, far) blend between two colors and take a scale and a seed.
+///
+/// Materials are "lambertian" (albedo), "metal" (albedo, fuzz), "dialectric"
+/// (refraction_index, and optionally the absorption per unit of distance
+/// inside as a color), "diffuse_light" (emit) and for volumes "isotropic"
+/// (albedo) and "henyey_greenstein" (albedo, g for the anisotropy), with
+/// albedo being a color or the name of a texture. The microfacet materials
+/// "rough_conductor" (a preset of "aluminium", "copper", "gold", "iron" or
+/// "silver", or the complex refraction index as colors eta and k) and
+/// "rough_dielectric" (refraction_index) take a roughness between 0 and 1.
+/// The "principled" material combines them all: a base_color (color or
+/// texture) with metallic, roughness (default 0.5), specular (default 0.5),
+/// sheen, clearcoat and transmission between 0 and 1, and a
+/// refraction_index for the transmission.
+///
+/// Objects are "sphere" (center, radius, negative for a hollow sphere),
+/// "moving_sphere" (start, end, radius; moving from start at time 0 to end at
+/// time 1, blurred by the camera's shutter_open and shutter_close), "quad"
+/// (corner and the edges u and v), "disk" (center, normal, radius), "box" (the
+/// corners min and max), "plane" (point, normal), "triangle" (vertices and
+/// optionally per-vertex normals), "voxel_grid" (path to a voxel grid header,
+/// min and max corner of the box it spans, density_scale, optionally a color
+/// for emission) and "obj" (path to a Wavefront OBJ model, using its MTL
+/// materials unless a material is given). Every object can be transformed with
+/// "scale" (a number or one per axis), "rotate" (degrees around x, y and z) and
+/// "translate". An object with a "density" becomes a volume like fog or smoke,
+/// bounded by its shape and scattering with its material.
+///
+/// Spheres, quads, disks and triangles with a "diffuse_light" material are
+/// sampled directly as lights, unless they are transformed or volumes.
+pub struct Scene {
+    pub world: HittableList,
+    /// Objects of the world that get sampled directly as light sources
+    pub lights: HittableList,
+    pub camera: CameraBuilder,
+    pub samples_per_pixel: u32,
+    pub max_ray_depth: u32,
+    pub background: SharedBackground,
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path).map_err(|error| SceneError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        Self::parse(&source, directory)
+    }
+
+    /// Parses a scene file's contents. Paths in the scene, like those of
+    /// models, are relative to 'directory'.
+    pub fn parse(source: &str, directory: &Path) -> Result<Self, SceneError> {
+        let sections = parser::parse(source)?;
+
+        let mut camera_builder = CameraBuilder::default();
+        let mut samples_per_pixel = DEFAULT_SAMPLES_PER_PIXEL;
+        let mut max_ray_depth = DEFAULT_MAX_RAY_DEPTH;
+        let mut background: SharedBackground = Arc::new(Gradient);
+        let mut textures = HashMap::new();
+        let mut materials = HashMap::new();
+        let mut world = HittableList::default();
+        let mut lights = HittableList::default();
+        let mut light_materials = HashSet::new();
+
+        for section in &sections {
+            let table = Table(section);
+            match (section.name.as_str(), section.is_array_item) {
+                ("", false) => table.check_keys(&[])?,
+                ("render", false) => {
+                    table.check_keys(&["samples_per_pixel", "max_depth", "background"])?;
+                    samples_per_pixel = table.u32("samples_per_pixel", samples_per_pixel)?;
+                    max_ray_depth = table.u32("max_depth", max_ray_depth)?;
+                    background = table.background(background)?;
+                }
+                ("camera", false) => camera_builder = table.camera_builder()?,
+                ("background", false) => background = table.environment(directory)?,
+                (name, false) if name.starts_with("textures.") => {
+                    let texture_name = &name["textures.".len()..];
+                    textures.insert(
+                        texture_name.to_string(),
+                        table.texture(&textures, directory)?,
+                    );
+                }
+                (name, false) if name.starts_with("materials.") => {
+                    let material_name = &name["materials.".len()..];
+                    materials.insert(material_name.to_string(), table.material(&textures)?);
+                    match table.string("type")? {
+                        "diffuse_light" => light_materials.insert(material_name.to_string()),
+                        _ => light_materials.remove(material_name),
+                    };
+                }
+                ("objects", true) => {
+                    let object = table.object(&materials, directory)?;
+                    if table.is_light(&light_materials)? {
+                        lights.add(Arc::clone(&object));
+                    }
+                    world.add(object);
+                }
+                _ => {
+                    return Err(SceneError::invalid(
+                        section.line,
+                        format!("unknown table {}", section.header()),
+                    ));
+                }
+            }
+        }
+
+        Ok(Self {
+            world,
+            lights,
+            camera: camera_builder,
+            samples_per_pixel,
+            max_ray_depth,
+            background,
+        })
+    }
+
+    pub fn into_renderer(self) -> Renderer {
+        let world = Arc::new(BvhNode::new(self.world));
+        Renderer::new(
+            world,
+            self.camera.finalize(),
+            self.samples_per_pixel,
+            self.max_ray_depth,
+        )
+        .with_background(self.background)
+        .with_lights(self.lights)
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io { path: PathBuf, error: io::Error },
+    Invalid { line: usize, message: String },
+}
+
+impl SceneError {
+    fn invalid(line: usize, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => {
+                write!(f, "could not read scene file {}: {}", path.display(), error)
+            }
+            Self::Invalid { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/// Typed access to the entries of a section.
+struct Table<'a>(&'a Section);
+
+impl Table<'_> {
+    fn check_keys(&self, allowed: &[&str]) -> Result<(), SceneError> {
+        for entry in &self.0.entries {
+            if !allowed.contains(&entry.key.as_str()) {
+                let message = match self.0.name.is_empty() {
+                    true => format!("unexpected key '{}' outside of a table", entry.key),
+                    false => format!("unknown key '{}' in {}", entry.key, self.0.header()),
+                };
+                return Err(SceneError::invalid(entry.line, message));
+            }
+        }
+        Ok(())
+    }
+
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        self.0.entries.iter().find(|entry| entry.key == key)
+    }
+
+    fn required(&self, key: &str) -> Result<&Entry, SceneError> {
+        self.entry(key).ok_or_else(|| {
+            SceneError::invalid(
+                self.0.line,
+                format!("{} is missing the key '{}'", self.0.header(), key),
+            )
+        })
+    }
+
+    fn f32(&self, key: &str, default: f32) -> Result<f32, SceneError> {
+        match self.entry(key) {
+            Some(entry) => Self::as_number(entry).map(|number| number as f32),
+            None => Ok(default),
+        }
+    }
+
+    fn required_f32(&self, key: &str) -> Result<f32, SceneError> {
+        Self::as_number(self.required(key)?).map(|number| number as f32)
+    }
+
+    fn u32(&self, key: &str, default: u32) -> Result<u32, SceneError> {
+        let Some(entry) = self.entry(key) else {
+            return Ok(default);
+        };
+        let number = Self::as_number(entry)?;
+        if number.fract() != 0.0 || number < 1.0 || number > u32::MAX as f64 {
+            return Err(SceneError::invalid(
+                entry.line,
+                format!("'{key}' has to be a positive whole number"),
+            ));
+        }
+        Ok(number as u32)
+    }
+
+    /// A seed for the random number generators, any whole number from 0
+    /// on. Numbers are read as floats, so seeds above 2^53 get rounded.
+    fn seed(&self, key: &str, default: u64) -> Result<u64, SceneError> {
+        let Some(entry) = self.entry(key) else {
+            return Ok(default);
+        };
+        let number = Self::as_number(entry)?;
+        if number.fract() != 0.0 || number < 0.0 || number > u64::MAX as f64 {
+            return Err(SceneError::invalid(
+                entry.line,
+                format!("'{key}' has to be a whole number of at least 0"),
+            ));
+        }
+        Ok(number as u64)
+    }
+
+    fn string(&self, key: &str) -> Result<&str, SceneError> {
+        let entry = self.required(key)?;
+        match &entry.value {
+            Value::String(string) => Ok(string),
+            other => Err(Self::type_error(entry, "a string", other)),
+        }
+    }
+
+    fn optional_string<'b>(&'b self, key: &str, default: &'b str) -> Result<&'b str, SceneError> {
+        match self.entry(key) {
+            Some(_) => self.string(key),
+            None => Ok(default),
+        }
+    }
+
+    fn unknown_option(&self, key: &str, value: &str, first: &str, second: &str) -> SceneError {
+        SceneError::invalid(
+            self.entry(key).map_or(self.0.line, |entry| entry.line),
+            format!("'{key}' has to be \"{first}\" or \"{second}\", found \"{value}\""),
+        )
+    }
+
+    fn vec3(&self, key: &str, default: Vec3) -> Result<Vec3, SceneError> {
+        match self.entry(key) {
+            Some(entry) => Self::as_triple(entry).map(|[x, y, z]| Vec3::new(x, y, z)),
+            None => Ok(default),
+        }
+    }
+
+    fn point(&self, key: &str, default: Point3) -> Result<Point3, SceneError> {
+        match self.entry(key) {
+            Some(entry) => Self::as_triple(entry).map(|[x, y, z]| Point3::new(x, y, z)),
+            None => Ok(default),
+        }
+    }
+
+    fn required_vec3(&self, key: &str) -> Result<Vec3, SceneError> {
+        let entry = self.required(key)?;
+        let [x, y, z] = Self::as_triple(entry)?;
+        match x == 0.0 && y == 0.0 && z == 0.0 {
+            true => Err(SceneError::invalid(
+                entry.line,
+                format!("'{key}' can't be a zero vector"),
+            )),
+            false => Ok(Vec3::new(x, y, z)),
+        }
+    }
+
+    fn required_point(&self, key: &str) -> Result<Point3, SceneError> {
+        Self::as_triple(self.required(key)?).map(|[x, y, z]| Point3::new(x, y, z))
+    }
+
+    fn color(&self, key: &str) -> Result<Color, SceneError> {
+        Self::as_triple(self.required(key)?).map(|[r, g, b]| Color::new(r, g, b))
+    }
+
+    fn as_number(entry: &Entry) -> Result<f64, SceneError> {
+        match entry.value {
+            Value::Number(number) if is_finite(number) => Ok(number),
+            Value::Number(_) => Err(SceneError::invalid(
+                entry.line,
+                format!("'{}' has to be a finite number", entry.key),
+            )),
+            ref other => Err(Self::type_error(entry, "a number", other)),
+        }
+    }
+
+    fn as_triple(entry: &Entry) -> Result<[f32; 3], SceneError> {
+        let error = || {
+            SceneError::invalid(
+                entry.line,
+                format!("'{}' has to be an array of three numbers", entry.key),
+            )
+        };
+
+        let Value::Array(values) = &entry.value else {
+            return Err(error());
+        };
+        match values.as_slice() {
+            [Value::Number(a), Value::Number(b), Value::Number(c)]
+                if [a, b, c].into_iter().all(|&number| is_finite(number)) =>
+            {
+                Ok([*a as f32, *b as f32, *c as f32])
+            }
+            _ => Err(error()),
+        }
+    }
+
+    /// Reads an array of three arrays of three numbers, e.g. the vertices of
+    /// a triangle.
+    fn triples(&self, key: &str) -> Result<[[f32; 3]; 3], SceneError> {
+        let entry = self.required(key)?;
+        let error = || {
+            SceneError::invalid(
+                entry.line,
+                format!("'{key}' has to be an array of three arrays of three numbers"),
+            )
+        };
+
+        let Value::Array(values) = &entry.value else {
+            return Err(error());
+        };
+        if values.len() != 3 {
+            return Err(error());
+        }
+
+        let mut triples = [[0.0; 3]; 3];
+        for (triple, value) in triples.iter_mut().zip(values) {
+            let element = Entry {
+                key: key.to_string(),
+                value: value.clone(),
+                line: entry.line,
+            };
+            *triple = Self::as_triple(&element).map_err(|_| error())?;
+        }
+        Ok(triples)
+    }
+
+    fn type_error(entry: &Entry, expected: &str, found: &Value) -> SceneError {
+        SceneError::invalid(
+            entry.line,
+            format!(
+                "'{}' has to be {}, found {}",
+                entry.key,
+                expected,
+                found.type_name()
+            ),
+        )
+    }
+
+    fn background(&self, default: SharedBackground) -> Result<SharedBackground, SceneError> {
+        let Some(entry) = self.entry("background") else {
+            return Ok(default);
+        };
+        match &entry.value {
+            Value::String(name) if name == "gradient" => Ok(Arc::new(Gradient)),
+            Value::Array(_) => Self::as_triple(entry)
+                .map(|[r, g, b]| Arc::new(Solid::new(Color::new(r, g, b))) as SharedBackground),
+            _ => Err(SceneError::invalid(
+                entry.line,
+                "'background' has to be \"gradient\" or a color",
+            )),
+        }
+    }
+
+    /// The [background] table, for backgrounds that need more than the
+    /// single value the render table's 'background' takes.
+    fn environment(&self, directory: &Path) -> Result<SharedBackground, SceneError> {
+        match self.string("type")? {
+            "gradient" => {
+                self.check_keys(&["type"])?;
+                Ok(Arc::new(Gradient))
+            }
+            "solid" => {
+                self.check_keys(&["type", "color"])?;
+                Ok(Arc::new(Solid::new(self.color("color")?)))
+            }
+            "environment_map" => {
+                self.check_keys(&["type", "path", "rotation", "intensity"])?;
+                let path = directory.join(self.string("path")?);
+                let map = EnvironmentMap::load(&path).map_err(|error| {
+                    SceneError::invalid(self.required("path").unwrap().line, error.to_string())
+                })?;
+                Ok(Arc::new(
+                    map.with_rotation(self.f32("rotation", 0.0)?)
+                        .with_intensity(self.f32("intensity", 1.0)?),
+                ))
+            }
+            "sky" => {
+                self.check_keys(&[
+                    "type",
+                    "sun_direction",
+                    "turbidity",
+                    "ground_albedo",
+                    "sun_size",
+                    "intensity",
+                ])?;
+                let turbidity = self.f32("turbidity", 3.0)?;
+                if !(1.7..=10.0).contains(&turbidity) {
+                    return Err(SceneError::invalid(
+                        self.required("turbidity")?.line,
+                        "'turbidity' has to be between 1.7 and 10",
+                    ));
+                }
+
+                let mut sky = Sky::new(self.required_vec3("sun_direction")?, turbidity)
+                    .with_sun_size(self.f32("sun_size", background::SUN_SIZE)?)
+                    .with_intensity(self.f32("intensity", 1.0)?);
+                if self.entry("ground_albedo").is_some() {
+                    sky = sky.with_ground_albedo(self.color("ground_albedo")?);
+                }
+                Ok(Arc::new(sky))
+            }
+            other => Err(SceneError::invalid(
+                self.required("type")?.line,
+                format!("unknown background type '{other}'"),
+            )),
+        }
+    }
+
+    fn camera_builder(&self) -> Result<CameraBuilder, SceneError> {
+        self.check_keys(&[
+            "image_width",
+            "aspect_ratio",
+            "vertical_fov",
+            "position",
+            "look_at",
+            "view_up",
+            "focus_distance",
+            "defocus_angle",
+            "shutter_open",
+            "shutter_close",
+        ])?;
+
+        let default = CameraBuilder::default();
+        let image_width = self.u32("image_width", default.image_width)?;
+        let aspect_ratio = self.positive("aspect_ratio", default.aspect_ratio)?;
+        let vertical_fov = self.f32("vertical_fov", default.vertical_fov)?;
+        if vertical_fov <= 0.0 || vertical_fov >= 180.0 {
+            return Err(SceneError::invalid(
+                self.required("vertical_fov")?.line,
+                "'vertical_fov' has to be between 0 and 180 degrees",
+            ));
+        }
+
+        // The same height as the camera calculates, which the renderer
+        // allocates the image for
+        let image_height = (image_width as f64 / aspect_ratio as f64).round().max(1.0);
+        if image_height > u32::MAX as f64 {
+            return Err(self.image_size_error(image_width, image_height));
+        }
+        reading::pixel_count(image_width as usize, image_height as usize)
+            .map_err(|_| self.image_size_error(image_width, image_height))?;
+
+        Ok(CameraBuilder {
+            image_width,
+            aspect_ratio,
+            vertical_fov,
+            position: self.point("position", default.position)?,
+            look_at: self.point("look_at", default.look_at)?,
+            view_up: self.vec3("view_up", default.view_up)?,
+            focus_distance: self.f32("focus_distance", default.focus_distance)?,
+            defocus_angle: self.f32("defocus_angle", default.defocus_angle)?,
+            shutter_open: self.f32("shutter_open", default.shutter_open)?,
+            shutter_close: self.f32("shutter_close", default.shutter_close)?,
+        })
+    }
+
+    fn image_size_error(&self, width: u32, height: f64) -> SceneError {
+        SceneError::invalid(
+            self.0.line,
+            format!("an image of {width}x{height} pixels is too large to render"),
+        )
+    }
+
+    fn texture(
+        &self,
+        textures: &HashMap<String, SharedTexture>,
+        directory: &Path,
+    ) -> Result<SharedTexture, SceneError> {
+        let texture: SharedTexture = match self.string("type")? {
+            "solid" => {
+                self.check_keys(&["type", "color"])?;
+                Arc::new(SolidColor::new(self.color("color")?))
+            }
+            "checker" => {
+                self.check_keys(&["type", "scale", "even", "odd"])?;
+                let scale = self.f32("scale", 1.0)?;
+                if scale <= 0.0 {
+                    return Err(SceneError::invalid(
+                        self.required("scale")?.line,
+                        "'scale' has to be positive",
+                    ));
+                }
+                let even = self.texture_reference("even", textures)?;
+                let odd = self.texture_reference("odd", textures)?;
+                Arc::new(Checker::new(scale, even, odd))
+            }
+            "uv_checker" => {
+                self.check_keys(&["type", "columns", "rows", "even", "odd"])?;
+                let columns = self.u32("columns", 8)?;
+                let rows = self.u32("rows", columns)?;
+                let even = self.texture_reference("even", textures)?;
+                let odd = self.texture_reference("odd", textures)?;
+                Arc::new(UvChecker::new(columns, rows, even, odd))
+            }
+            "turbulence" => {
+                self.check_keys(&["type", "seed", "scale", "low", "high"])?;
+                let (seed, scale) = self.noise_parameters()?;
+                Arc::new(Turbulence::new(
+                    seed,
+                    scale,
+                    self.color("low")?,
+                    self.color("high")?,
+                ))
+            }
+            "marble" => {
+                self.check_keys(&["type", "seed", "scale", "base", "veins"])?;
+                let (seed, scale) = self.noise_parameters()?;
+                Arc::new(Marble::new(
+                    seed,
+                    scale,
+                    self.color("base")?,
+                    self.color("veins")?,
+                ))
+            }
+            "wood" => {
+                self.check_keys(&["type", "seed", "scale", "light", "dark"])?;
+                let (seed, scale) = self.noise_parameters()?;
+                Arc::new(Wood::new(
+                    seed,
+                    scale,
+                    self.color("light")?,
+                    self.color("dark")?,
+                ))
+            }
+            "worley" => {
+                self.check_keys(&["type", "seed", "scale", "near", "far"])?;
+                let (seed, scale) = self.noise_parameters()?;
+                Arc::new(Worley::new(
+                    seed,
+                    scale,
+                    self.color("near")?,
+                    self.color("far")?,
+                ))
+            }
+            "image" => {
+                self.check_keys(&["type", "path", "filter", "wrap", "color_space"])?;
+                let path = directory.join(self.string("path")?);
+                let loaded = match self.optional_string("color_space", "srgb")? {
+                    "srgb" => ImageTexture::load(&path),
+                    "linear" => ImageTexture::load_linear(&path),
+                    other => {
+                        return Err(self.unknown_option("color_space", other, "srgb", "linear"))
+                    }
+                };
+                let texture = loaded.map_err(|error| {
+                    SceneError::invalid(self.required("path").unwrap().line, error.to_string())
+                })?;
+
+                let filter = match self.optional_string("filter", "bilinear")? {
+                    "bilinear" => Filter::Bilinear,
+                    "nearest" => Filter::Nearest,
+                    other => {
+                        return Err(self.unknown_option("filter", other, "bilinear", "nearest"))
+                    }
+                };
+                let wrap = match self.optional_string("wrap", "repeat")? {
+                    "repeat" => Wrap::Repeat,
+                    "clamp" => Wrap::Clamp,
+                    other => return Err(self.unknown_option("wrap", other, "repeat", "clamp")),
+                };
+                Arc::new(texture.with_filter(filter).with_wrap(wrap))
+            }
+            other => {
+                return Err(SceneError::invalid(
+                    self.required("type")?.line,
+                    format!("unknown texture type '{other}'"),
+                ))
+            }
+        };
+        Ok(texture)
+    }
+
+    /// The seed and scale shared by the procedural textures.
+    fn noise_parameters(&self) -> Result<(u64, f32), SceneError> {
+        let seed = self.seed("seed", 1)?;
+        let scale = self.f32("scale", 1.0)?;
+        Ok((seed, scale))
+    }
+
+    fn material(
+        &self,
+        textures: &HashMap<String, SharedTexture>,
+    ) -> Result<SharedMaterial, SceneError> {
+        let material: SharedMaterial = match self.string("type")? {
+            "lambertian" => {
+                self.check_keys(&["type", "albedo"])?;
+                Arc::new(Lambertian::textured(
+                    self.texture_reference("albedo", textures)?,
+                ))
+            }
+            "metal" => {
+                self.check_keys(&["type", "albedo", "fuzz"])?;
+                let albedo = self.texture_reference("albedo", textures)?;
+                Arc::new(Metal::textured(albedo, self.f32("fuzz", 0.0)?))
+            }
+            "diffuse_light" => {
+                self.check_keys(&["type", "emit"])?;
+                Arc::new(DiffuseLight::new(self.color("emit")?))
+            }
+            "henyey_greenstein" => {
+                self.check_keys(&["type", "albedo", "g"])?;
+                let albedo = self.texture_reference("albedo", textures)?;
+                let g = self.f32("g", 0.0)?;
+                if !(-1.0..=1.0).contains(&g) {
+                    return Err(SceneError::invalid(
+                        self.required("g")?.line,
+                        "'g' has to be between -1 and 1",
+                    ));
+                }
+                Arc::new(HenyeyGreenstein::textured(albedo, g))
+            }
+            "isotropic" => {
+                self.check_keys(&["type", "albedo"])?;
+                Arc::new(Isotropic::textured(
+                    self.texture_reference("albedo", textures)?,
+                ))
+            }
+            "dialectric" | "dielectric" => {
+                self.check_keys(&["type", "refraction_index", "absorption"])?;
+                let mut dialectric = Dialectric::new(self.required_positive("refraction_index")?);
+                if let Some(entry) = self.entry("absorption") {
+                    let absorption = self.color("absorption")?;
+                    if absorption.r < 0.0 || absorption.g < 0.0 || absorption.b < 0.0 {
+                        return Err(SceneError::invalid(
+                            entry.line,
+                            "'absorption' can't be negative",
+                        ));
+                    }
+                    dialectric = dialectric.with_absorption(absorption);
+                }
+                Arc::new(dialectric)
+            }
+            "rough_conductor" => {
+                self.check_keys(&["type", "preset", "eta", "k", "roughness"])?;
+                let roughness = self.fraction("roughness", 0.0)?;
+                match self.entry("preset") {
+                    Some(entry) => {
+                        let name = self.string("preset")?;
+                        let conductor = Conductor::from_name(name).ok_or_else(|| {
+                            SceneError::invalid(
+                                entry.line,
+                                format!("unknown conductor preset '{name}'"),
+                            )
+                        })?;
+                        Arc::new(RoughConductor::preset(conductor, roughness))
+                    }
+                    None => Arc::new(RoughConductor::new(
+                        self.color("eta")?,
+                        self.color("k")?,
+                        roughness,
+                    )),
+                }
+            }
+            "rough_dialectric" | "rough_dielectric" => {
+                self.check_keys(&["type", "refraction_index", "roughness"])?;
+                Arc::new(RoughDielectric::new(
+                    self.required_positive("refraction_index")?,
+                    self.fraction("roughness", 0.0)?,
+                ))
+            }
+            "principled" => {
+                self.check_keys(&[
+                    "type",
+                    "base_color",
+                    "metallic",
+                    "roughness",
+                    "specular",
+                    "sheen",
+                    "clearcoat",
+                    "transmission",
+                    "refraction_index",
+                ])?;
+                let base_color = self.texture_reference("base_color", textures)?;
+                Arc::new(
+                    Principled::textured(base_color)
+                        .with_metallic(self.fraction("metallic", 0.0)?)
+                        .with_roughness(self.fraction("roughness", 0.5)?)
+                        .with_specular(self.fraction("specular", 0.5)?)
+                        .with_sheen(self.fraction("sheen", 0.0)?)
+                        .with_clearcoat(self.fraction("clearcoat", 0.0)?)
+                        .with_transmission(self.fraction("transmission", 0.0)?)
+                        .with_refraction_index(self.positive("refraction_index", 1.5)?),
+                )
+            }
+            other => {
+                return Err(SceneError::invalid(
+                    self.required("type")?.line,
+                    format!("unknown material type '{other}'"),
+                ))
+            }
+        };
+        Ok(material)
+    }
+
+    /// A number larger than 0, like a refraction index.
+    fn positive(&self, key: &str, default: f32) -> Result<f32, SceneError> {
+        let value = self.f32(key, default)?;
+        self.check_positive(key, value)
+    }
+
+    fn required_positive(&self, key: &str) -> Result<f32, SceneError> {
+        let value = self.required_f32(key)?;
+        self.check_positive(key, value)
+    }
+
+    fn check_positive(&self, key: &str, value: f32) -> Result<f32, SceneError> {
+        match value > 0.0 {
+            true => Ok(value),
+            false => Err(SceneError::invalid(
+                self.required(key)?.line,
+                format!("'{key}' has to be larger than 0"),
+            )),
+        }
+    }
+
+    /// A material parameter between 0 and 1, like the roughness.
+    fn fraction(&self, key: &str, default: f32) -> Result<f32, SceneError> {
+        let value = self.f32(key, default)?;
+        if !(0.0..=1.0).contains(&value) {
+            return Err(SceneError::invalid(
+                self.required(key)?.line,
+                format!("'{key}' has to be between 0 and 1"),
+            ));
+        }
+        Ok(value)
+    }
+
+    fn object(
+        &self,
+        materials: &HashMap<String, SharedMaterial>,
+        directory: &Path,
+    ) -> Result<SharedHittable, SceneError> {
+        let shape = self.shape(materials, directory)?;
+        let object = self.volume(shape, materials)?;
+        self.transformed(object)
+    }
+
+    /// Whether the object is one of the shapes that can be sampled as a
+    /// light and emits light with a "diffuse_light" material. Transformed
+    /// objects and volumes are only lit by rays bouncing into them.
+    fn is_light(&self, light_materials: &HashSet<String>) -> Result<bool, SceneError> {
+        let sampleable = matches!(
+            self.string("type")?,
+            "sphere" | "quad" | "disk" | "triangle"
+        );
+        let plain = TRANSFORM_KEYS
+            .iter()
+            .chain(&["density"])
+            .all(|key| self.entry(key).is_none());
+        Ok(sampleable && plain && light_materials.contains(self.string("material")?))
+    }
+
+    fn shape(
+        &self,
+        materials: &HashMap<String, SharedMaterial>,
+        directory: &Path,
+    ) -> Result<SharedHittable, SceneError> {
+        match self.string("type")? {
+            "sphere" => {
+                self.check_object_keys(&["type", "center", "radius", "material"])?;
+                let center = self.required_point("center")?;
+                let radius = self.sphere_radius()?;
+                let material = self.material_reference(materials)?;
+                Ok(Arc::new(Sphere::new(center, radius, material)))
+            }
+            "moving_sphere" => {
+                self.check_object_keys(&["type", "start", "end", "radius", "material"])?;
+                let start = self.required_point("start")?;
+                let end = self.required_point("end")?;
+                let radius = self.sphere_radius()?;
+                let material = self.material_reference(materials)?;
+                Ok(Arc::new(MovingSphere::new(start, end, radius, material)))
+            }
+            "quad" => {
+                self.check_object_keys(&["type", "corner", "u", "v", "material"])?;
+                let corner = self.required_point("corner")?;
+                let u = self.required_vec3("u")?;
+                let v = self.required_vec3("v")?;
+                let material = self.material_reference(materials)?;
+                Ok(Arc::new(Quad::new(corner, u, v, material)))
+            }
+            "disk" => {
+                self.check_object_keys(&["type", "center", "normal", "radius", "material"])?;
+                let center = self.required_point("center")?;
+                let normal = self.required_vec3("normal")?;
+                let radius = self.required_positive("radius")?;
+                let material = self.material_reference(materials)?;
+                Ok(Arc::new(Disk::new(center, normal, radius, material)))
+            }
+            "box" => {
+                self.check_object_keys(&["type", "min", "max", "material"])?;
+                let min = self.required_point("min")?;
+                let max = self.required_point("max")?;
+                let material = self.material_reference(materials)?;
+                Ok(Arc::new(Cuboid::new(min, max, material)))
+            }
+            "plane" => {
+                self.check_object_keys(&["type", "point", "normal", "material"])?;
+                let point = self.required_point("point")?;
+                let normal = self.required_vec3("normal")?;
+                let material = self.material_reference(materials)?;
+                Ok(Arc::new(Plane::new(point, normal, material)))
+            }
+            "voxel_grid" => {
+                self.check_object_keys(&[
+                    "type",
+                    "path",
+                    "min",
+                    "max",
+                    "density_scale",
+                    "emission",
+                    "material",
+                ])?;
+                let path = directory.join(self.string("path")?);
+                let grid = VoxelGrid::load(&path).map_err(|error| {
+                    SceneError::invalid(self.required("path").unwrap().line, error.to_string())
+                })?;
+                let min = self.required_point("min")?;
+                let max = self.required_point("max")?;
+                let density_scale = self.f32("density_scale", 1.0)?;
+                let material = self.material_reference(materials)?;
+
+                let mut medium = GridMedium::new(Arc::new(grid), min, max, density_scale, material);
+                if self.entry("emission").is_some() {
+                    medium = medium.with_emission(self.color("emission")?);
+                }
+                Ok(Arc::new(medium))
+            }
+            "triangle" => {
+                self.check_object_keys(&["type", "vertices", "normals", "material"])?;
+                let [a, b, c] = self
+                    .triples("vertices")?
+                    .map(|[x, y, z]| Point3::new(x, y, z));
+                let material = self.material_reference(materials)?;
+                let mut triangle = Triangle::new(a, b, c, material);
+                if self.entry("normals").is_some() {
+                    let normals = self.triples("normals")?.map(|[x, y, z]| Vec3::new(x, y, z));
+                    triangle = triangle.with_normals(normals);
+                }
+                Ok(Arc::new(triangle))
+            }
+            "obj" => {
+                self.check_object_keys(&["type", "path", "material"])?;
+                let path = directory.join(self.string("path")?);
+                let model = ObjModel::load(&path).map_err(|error| {
+                    SceneError::invalid(self.required("path").unwrap().line, error.to_string())
+                })?;
+
+                // Without a material, the ones from the model's MTL files get used
+                let mut triangles = HittableList::default();
+                match self.entry("material") {
+                    Some(_) => {
+                        let material = self.material_reference(materials)?;
+                        for obj_mesh in model.meshes {
+                            let mesh = obj_mesh.mesh.with_material(Arc::clone(&material));
+                            for triangle in mesh.triangles() {
+                                triangles.add(triangle);
+                            }
+                        }
+                    }
+                    None => model.add_to(&mut triangles),
+                }
+                Ok(Arc::new(BvhNode::new(triangles)))
+            }
+            other => Err(SceneError::invalid(
+                self.required("type")?.line,
+                format!("unknown object type '{other}'"),
+            )),
+        }
+    }
+
+    fn check_object_keys(&self, allowed: &[&str]) -> Result<(), SceneError> {
+        let allowed: Vec<&str> = allowed
+            .iter()
+            .chain(&TRANSFORM_KEYS)
+            .chain(&["density"])
+            .copied()
+            .collect();
+        self.check_keys(&allowed)
+    }
+
+    /// With a density, the object only serves as the boundary of a volume,
+    /// which scatters light with the object's material.
+    fn volume(
+        &self,
+        shape: SharedHittable,
+        materials: &HashMap<String, SharedMaterial>,
+    ) -> Result<SharedHittable, SceneError> {
+        let Some(entry) = self.entry("density") else {
+            return Ok(shape);
+        };
+        let density = Self::as_number(entry)? as f32;
+        if density <= 0.0 {
+            return Err(SceneError::invalid(
+                entry.line,
+                "'density' has to be positive",
+            ));
+        }
+
+        let phase_function = self.material_reference(materials)?;
+        Ok(Arc::new(ConstantMedium::new(
+            shape,
+            density,
+            phase_function,
+        )))
+    }
+
+    /// Wraps the object in an instance if any of the transform keys are
+    /// given. The object gets scaled first, then rotated by the given
+    /// degrees around the x, y and z axes in that order, and then translated.
+    fn transformed(&self, object: SharedHittable) -> Result<SharedHittable, SceneError> {
+        if TRANSFORM_KEYS.iter().all(|key| self.entry(key).is_none()) {
+            return Ok(object);
+        }
+
+        let mut transform = Transform::identity();
+        if let Some(entry) = self.entry("scale") {
+            let factors = match entry.value {
+                Value::Number(factor) => Vec3::new(factor as f32, factor as f32, factor as f32),
+                _ => Self::as_triple(entry).map(|[x, y, z]| Vec3::new(x, y, z))?,
+            };
+            if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+                return Err(SceneError::invalid(entry.line, "'scale' can't be zero"));
+            }
+            transform = transform.then(Transform::scaling(factors));
+        }
+        if self.entry("rotate").is_some() {
+            let degrees = self.vec3("rotate", Vec3::zero())?;
+            transform = transform
+                .then(Transform::rotation_x(degrees.x))
+                .then(Transform::rotation_y(degrees.y))
+                .then(Transform::rotation_z(degrees.z));
+        }
+        if self.entry("translate").is_some() {
+            let offset = self.vec3("translate", Vec3::zero())?;
+            transform = transform.then(Transform::translation(offset));
+        }
+
+        Ok(Arc::new(Instance::new(object, transform)))
+    }
+
+    /// Reads either a color, which becomes a solid texture, or the name of a
+    /// texture defined earlier.
+    fn texture_reference(
+        &self,
+        key: &str,
+        textures: &HashMap<String, SharedTexture>,
+    ) -> Result<SharedTexture, SceneError> {
+        let entry = self.required(key)?;
+        match &entry.value {
+            Value::String(name) => textures.get(name).cloned().ok_or_else(|| {
+                SceneError::invalid(
+                    entry.line,
+                    format!("unknown texture '{name}', textures have to be defined before use"),
+                )
+            }),
+            Value::Array(_) => {
+                let [r, g, b] = Self::as_triple(entry)?;
+                Ok(Arc::new(SolidColor::new(Color::new(r, g, b))))
+            }
+            other => Err(Self::type_error(entry, "a color or a texture name", other)),
+        }
+    }
+
+    /// A sphere's radius, which may be negative to turn the normals inward,
+    /// e.g. for the inside of a hollow glass sphere.
+    fn sphere_radius(&self) -> Result<f32, SceneError> {
+        let radius = self.required_f32("radius")?;
+        match radius != 0.0 {
+            true => Ok(radius),
+            false => Err(SceneError::invalid(
+                self.required("radius")?.line,
+                "'radius' can't be 0",
+            )),
+        }
+    }
+
+    fn material_reference(
+        &self,
+        materials: &HashMap<String, SharedMaterial>,
+    ) -> Result<SharedMaterial, SceneError> {
+        let name = self.string("material")?;
+        materials.get(name).cloned().ok_or_else(|| {
+            SceneError::invalid(
+                self.required("material")
+                    .map_or(self.0.line, |entry| entry.line),
+                format!("unknown material '{name}', materials have to be defined before use"),
+            )
+        })
+    }
+}
+
+/// Whether a number is finite even as the f32 everything gets converted to.
+fn is_finite(number: f64) -> bool {
+    (number as f32).is_finite()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(source: &str) -> String {
+        match Scene::parse(source, Path::new("")) {
+            Ok(_) => panic!("the scene parsed without an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    const MATERIAL: &str = "[materials.glass]\ntype = \"dielectric\"\nrefraction_index = 1.5\n";
+
+    #[test]
+    fn parses_a_scene() {
+        let scene = Scene::parse(
+            &format!(
+                "[camera]\naspect_ratio = 2\nvertical_fov = 90\n{MATERIAL}\
+                 [[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = -0.5\n\
+                 material = \"glass\"\n"
+            ),
+            Path::new(""),
+        )
+        .unwrap();
+        assert_eq!(scene.camera.aspect_ratio, 2.0);
+        assert_eq!(scene.camera.vertical_fov, 90.0);
+        assert_eq!(scene.world.len(), 1);
+    }
+
+    #[test]
+    fn rejects_non_finite_numbers() {
+        // Too large for an f32 is as bad as infinite
+        for value in ["+inf", "-inf", "+nan", "1e999", "1e300"] {
+            assert_eq!(
+                error_of(&format!(
+                    "[render]\nsamples_per_pixel = 10\n\n[camera]\nvertical_fov = {value}"
+                )),
+                "line 5: 'vertical_fov' has to be a finite number"
+            );
+        }
+        assert_eq!(
+            error_of("[camera]\nposition = [0, +nan, 0]"),
+            "line 2: 'position' has to be an array of three numbers"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_cameras() {
+        for value in ["0", "-1"] {
+            assert_eq!(
+                error_of(&format!("[camera]\naspect_ratio = {value}")),
+                "line 2: 'aspect_ratio' has to be larger than 0"
+            );
+        }
+        assert_eq!(
+            error_of("[camera]\nimage_width = 100\naspect_ratio = 0.00001"),
+            "line 1: an image of 100x10000000 pixels is too large to render"
+        );
+        assert!(error_of("[camera]\naspect_ratio = 1e-30").ends_with("too large to render"));
+        for value in ["0", "180", "-20", "270"] {
+            assert_eq!(
+                error_of(&format!("[camera]\nvertical_fov = {value}")),
+                "line 2: 'vertical_fov' has to be between 0 and 180 degrees"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        let sphere = |radius: &str| {
+            format!(
+                "{MATERIAL}[[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\n\
+                 radius = {radius}\nmaterial = \"glass\"\n"
+            )
+        };
+        assert_eq!(error_of(&sphere("0")), "line 7: 'radius' can't be 0");
+
+        let disk = "[materials.glass]\ntype = \"dielectric\"\nrefraction_index = 1.5\n\
+                    [[objects]]\ntype = \"disk\"\ncenter = [0, 0, 0]\nnormal = [0, 1, 0]\n\
+                    radius = -1\nmaterial = \"glass\"\n";
+        assert_eq!(error_of(disk), "line 8: 'radius' has to be larger than 0");
+
+        for material in ["dielectric", "rough_dielectric", "principled"] {
+            let source = format!(
+                "[materials.glass]\ntype = \"{material}\"\nbase_color = [1, 1, 1]\n\
+                 refraction_index = 0\n"
+            );
+            let source = match material {
+                "principled" => source,
+                _ => source.replace("base_color = [1, 1, 1]\n", ""),
+            };
+            assert!(
+                error_of(&source).ends_with("'refraction_index' has to be larger than 0"),
+                "{material}"
+            );
+        }
+    }
+}
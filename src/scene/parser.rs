@@ -0,0 +1,371 @@
+//! Parser for the subset of TOML used by scene files: comments, `[table]`
+//! and `[[array]]` headers with dotted names, and single line `key = value`
+//! pairs where a value is a string, number or array of values.
+
+use super::SceneError;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Number(_) => "a number",
+            Value::Array(_) => "an array",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+/// A header and the key value pairs that follow it. Keys before the first
+/// header end up in a section with an empty name.
+#[derive(Debug)]
+pub struct Section {
+    pub name: String,
+    pub is_array_item: bool,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Section {
+    /// The header as written in the file, e.g. "[camera]" or "[[objects]]".
+    pub fn header(&self) -> String {
+        match self.is_array_item {
+            true => format!("[[{}]]", self.name),
+            false => format!("[{}]", self.name),
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Vec<Section>, SceneError> {
+    let mut sections = vec![Section {
+        name: String::new(),
+        is_array_item: false,
+        line: 1,
+        entries: vec![],
+    }];
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: String| SceneError::invalid(line_number, message);
+
+        let mut cursor = Cursor::new(line);
+        cursor.skip_whitespace();
+        if cursor.at_end() {
+            continue;
+        }
+
+        if cursor.peek() == Some('[') {
+            let section = parse_header(&mut cursor, line_number).map_err(error)?;
+            let duplicate = !section.is_array_item
+                && sections
+                    .iter()
+                    .any(|other| !other.is_array_item && other.name == section.name);
+            if duplicate {
+                return Err(error(format!("table [{}] is defined twice", section.name)));
+            }
+            sections.push(section);
+            continue;
+        }
+
+        let key = cursor.parse_key().map_err(error)?;
+        cursor.skip_whitespace();
+        if !cursor.eat('=') {
+            return Err(error(format!("expected '=' after key '{key}'")));
+        }
+        let value = cursor.parse_value().map_err(error)?;
+        cursor.expect_end().map_err(error)?;
+
+        let section = sections.last_mut().unwrap();
+        if section.entries.iter().any(|entry| entry.key == key) {
+            return Err(error(format!("key '{key}' is defined twice")));
+        }
+        section.entries.push(Entry {
+            key,
+            value,
+            line: line_number,
+        });
+    }
+
+    Ok(sections)
+}
+
+fn parse_header(cursor: &mut Cursor, line: usize) -> Result<Section, String> {
+    cursor.eat('[');
+    let is_array_item = cursor.eat('[');
+
+    let mut parts = vec![];
+    loop {
+        cursor.skip_whitespace();
+        parts.push(cursor.parse_key()?);
+        cursor.skip_whitespace();
+        if !cursor.eat('.') {
+            break;
+        }
+    }
+
+    let closing = if is_array_item { "]]" } else { "]" };
+    for c in closing.chars() {
+        if !cursor.eat(c) {
+            return Err(format!("expected '{closing}' to close the header"));
+        }
+    }
+    cursor.expect_end()?;
+
+    Ok(Section {
+        name: parts.join("."),
+        is_array_item,
+        line,
+        entries: vec![],
+    })
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { rest: line }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        match self.peek() == Some(expected) {
+            true => {
+                self.advance();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+        if self.rest.starts_with('#') {
+            self.rest = "";
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    fn expect_end(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.at_end() {
+            true => Ok(()),
+            false => Err(format!("unexpected '{}'", self.rest.trim_end())),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let end = self.rest.find(|c| !predicate(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    fn parse_key(&mut self) -> Result<String, String> {
+        if self.peek() == Some('"') {
+            return self.parse_string();
+        }
+
+        let key = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        match key.is_empty() {
+            true => Err(format!("expected a key, found '{}'", self.rest.trim_end())),
+            false => Ok(key.to_string()),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.parse_string().map(Value::String),
+            Some('[') => self.parse_array(),
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                self.parse_number()
+            }
+            Some(_) => Err(format!("invalid value '{}'", self.rest.trim_end())),
+            None => Err("expected a value".to_string()),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.eat('"');
+        let mut string = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.advance() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some(c) => return Err(format!("unknown escape sequence '\\{c}'")),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some(c) => string.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let text =
+            self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | '_'));
+        text.replace('_', "")
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| format!("invalid number '{text}'"))
+    }
+
+    fn parse_array(&mut self) -> Result<Value, String> {
+        self.eat('[');
+        let mut values = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            if self.at_end() {
+                return Err("unterminated array, arrays have to fit on one line".to_string());
+            }
+
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            if self.at_end() {
+                return Err("unterminated array, arrays have to fit on one line".to_string());
+            }
+            if !self.eat(',') && self.peek() != Some(']') {
+                return Err("expected ',' or ']' in array".to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(source: &str) -> String {
+        parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_tables_and_array_items() {
+        let sections = parse(
+            "samples = 10\n\
+             # A comment\n\
+             [materials.ground]  # trailing comment\n\
+             type = \"lambertian\"\n\
+             \n\
+             [[objects]]\n\
+             center = [0, -1_000, 0.5]\n\
+             [[objects]]\n\
+             \"quoted key\" = [[1, 2], \"a\\tb\\\"\"]\n",
+        )
+        .unwrap();
+
+        let names: Vec<_> = sections.iter().map(Section::header).collect();
+        assert_eq!(
+            names,
+            ["[]", "[materials.ground]", "[[objects]]", "[[objects]]"]
+        );
+        assert_eq!(sections[1].line, 3);
+
+        let samples = &sections[0].entries[0];
+        assert_eq!(samples.key, "samples");
+        assert!(matches!(samples.value, Value::Number(n) if n == 10.0));
+
+        let kind = &sections[1].entries[0];
+        assert_eq!(kind.line, 4);
+        assert!(matches!(&kind.value, Value::String(s) if s == "lambertian"));
+
+        let Value::Array(center) = &sections[2].entries[0].value else {
+            panic!("center isn't an array");
+        };
+        let center: Vec<f64> = center
+            .iter()
+            .map(|value| match value {
+                Value::Number(n) => *n,
+                other => panic!("{} isn't a number", other.type_name()),
+            })
+            .collect();
+        assert_eq!(center, [0.0, -1000.0, 0.5]);
+
+        let quoted = &sections[3].entries[0];
+        assert_eq!(quoted.key, "quoted key");
+        let Value::Array(values) = &quoted.value else {
+            panic!("value isn't an array");
+        };
+        assert!(matches!(&values[0], Value::Array(inner) if inner.len() == 2));
+        assert!(matches!(&values[1], Value::String(s) if s == "a\tb\""));
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        assert_eq!(
+            error_of("[camera]\n[render]\n[camera]"),
+            "line 3: table [camera] is defined twice"
+        );
+        assert_eq!(
+            error_of("[camera]\nfov = 20\nfov = 30"),
+            "line 3: key 'fov' is defined twice"
+        );
+        // Array items can repeat, and so can keys in different items
+        assert!(parse("[[objects]]\na = 1\n[[objects]]\na = 2").is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(error_of("key 1"), "line 1: expected '=' after key 'key'");
+        assert_eq!(error_of("key = "), "line 1: expected a value");
+        assert_eq!(error_of("key = true"), "line 1: invalid value 'true'");
+        assert_eq!(error_of("key = 1.2.3"), "line 1: invalid number '1.2.3'");
+        assert_eq!(error_of("key = 1 2"), "line 1: unexpected '2'");
+        assert_eq!(error_of("key = \"open"), "line 1: unterminated string");
+        assert_eq!(
+            error_of("key = \"\\q\""),
+            "line 1: unknown escape sequence '\\q'"
+        );
+        assert_eq!(
+            error_of("\n\nkey = [1, 2"),
+            "line 3: unterminated array, arrays have to fit on one line"
+        );
+        assert_eq!(
+            error_of("key = [1 2]"),
+            "line 1: expected ',' or ']' in array"
+        );
+        assert_eq!(
+            error_of("[camera"),
+            "line 1: expected ']' to close the header"
+        );
+        assert_eq!(
+            error_of("[[objects]"),
+            "line 1: expected ']]' to close the header"
+        );
+        assert_eq!(error_of("[]"), "line 1: expected a key, found ']'");
+    }
+}
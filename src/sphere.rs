@@ -1,11 +1,16 @@
+use crate::aabb::Aabb;
+use crate::onb::Onb;
+use crate::random;
 use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedMaterial, Vec3};
 
+use std::f32::consts::PI;
 use std::sync::Arc;
 
 pub struct Sphere {
     center: Point3,
     radius: f32,
     material: SharedMaterial,
+    bounding_box: Aabb,
 }
 
 impl Sphere {
@@ -14,6 +19,7 @@ impl Sphere {
             center,
             radius,
             material,
+            bounding_box: bounding_box(center, radius),
         }
     }
 
@@ -24,36 +30,112 @@ impl Sphere {
     pub fn radius(&self) -> f32 {
         self.radius
     }
+
+    /// One minus the cosine of the half angle of the cone the sphere fills
+    /// as seen from 'origin', None if 'origin' is inside the sphere. Written
+    /// so it doesn't round to zero for small, distant spheres.
+    fn cone_extent(&self, origin: Point3) -> Option<f32> {
+        let ratio = self.radius * self.radius / (self.center - origin).length_squared();
+        match ratio < 1.0 {
+            true => Some(ratio / (1.0 + (1.0 - ratio).sqrt())),
+            false => None,
+        }
+    }
 }
 
 impl Hittable for Sphere {
     fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_squared();
-        let half_b = Vec3::dot(oc, ray.direction());
-        let c = oc.length_squared() - self.radius * self.radius;
+        intersect(
+            self.center,
+            self.radius,
+            &self.material,
+            ray,
+            allowed_t,
+            hit_rec,
+        )
+    }
 
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return false;
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let Some(extent) = self.cone_extent(origin) else {
+            return 0.0;
+        };
+        let ray = Ray::new(origin, direction);
+        match self.hit(&ray, Interval::new(0.001, f32::INFINITY)) {
+            Some(_) => 1.0 / (2.0 * PI * extent),
+            None => 0.0,
         }
-        let sqrt_discriminant = discriminant.sqrt();
+    }
+
+    /// Picks a direction uniformly within the cone of directions that
+    /// reach the sphere.
+    fn random_direction(&self, origin: Point3) -> Vec3 {
+        let to_center = self.center - origin;
+        let Some(extent) = self.cone_extent(origin) else {
+            return to_center;
+        };
+
+        let cos_theta = 1.0 - random::random() * extent;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random::random();
+        Onb::from_w(to_center).local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+}
 
-        // Find the nearest t that lies in the acceptable range
-        let mut t = (-half_b - sqrt_discriminant) / a;
+/// Box around a sphere, which may have a negative radius.
+pub(crate) fn bounding_box(center: Point3, radius: f32) -> Aabb {
+    let radius_vector = Vec3::new(radius, radius, radius);
+    Aabb::from_points(center - radius_vector, center + radius_vector)
+}
+
+/// Intersection with the sphere at 'center', shared by all spheres no matter
+/// how they get their center.
+pub(crate) fn intersect(
+    center: Point3,
+    radius: f32,
+    material: &SharedMaterial,
+    ray: &Ray,
+    allowed_t: Interval,
+    hit_rec: &mut HitRecord,
+) -> bool {
+    let oc = ray.origin() - center;
+    let a = ray.direction().length_squared();
+    let half_b = Vec3::dot(oc, ray.direction());
+    let c = oc.length_squared() - radius * radius;
+
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return false;
+    }
+    let sqrt_discriminant = discriminant.sqrt();
+
+    // Find the nearest t that lies in the acceptable range
+    let mut t = (-half_b - sqrt_discriminant) / a;
+    if !allowed_t.surrounds(t) {
+        t = (-half_b + sqrt_discriminant) / a;
         if !allowed_t.surrounds(t) {
-            t = (-half_b + sqrt_discriminant) / a;
-            if !allowed_t.surrounds(t) {
-                return false;
-            }
+            return false;
         }
+    }
 
-        hit_rec.t = t;
-        hit_rec.point = ray.at(t);
-        let outward_normal = (hit_rec.point - self.center) / self.radius;
-        hit_rec.set_face_normal(ray, outward_normal);
-        hit_rec.material = Some(Arc::clone(&self.material));
+    hit_rec.t = t;
+    hit_rec.point = ray.at(t);
+    let outward_normal = (hit_rec.point - center) / radius;
+    hit_rec.set_face_normal(ray, outward_normal);
+    (hit_rec.u, hit_rec.v) = uv((hit_rec.point - center) / radius.abs());
+    hit_rec.material = Some(Arc::clone(material));
 
-        true
-    }
+    true
+}
+
+/// Maps a point on the unit sphere to (u, v), with u going around the
+/// y axis starting at -x, and v from the bottom (-y) to the top (+y).
+fn uv(point: Vec3) -> (f32, f32) {
+    let theta = (-point.y).clamp(-1.0, 1.0).acos();
+    let phi = (-point.z).atan2(point.x) + PI;
+
+    (phi / (2.0 * PI), theta / PI)
 }
@@ -0,0 +1,17 @@
+mod checker;
+mod image;
+mod noise;
+mod solid_color;
+
+pub use checker::{Checker, UvChecker};
+pub(crate) use image::srgb_to_linear;
+pub use image::{Filter, ImageTexture, Wrap};
+pub use noise::{Marble, Turbulence, Wood, Worley};
+pub use solid_color::SolidColor;
+
+use crate::{Color, Point3};
+
+pub trait Texture {
+    /// Color at the surface coordinates (u, v) of the hit 'point'.
+    fn value(&self, u: f32, v: f32, point: Point3) -> Color;
+}
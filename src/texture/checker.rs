@@ -0,0 +1,67 @@
+use super::Texture;
+
+use crate::{Color, Point3, SharedTexture};
+
+/// Checker pattern of cubes in world space, so it doesn't depend on the
+/// surface parameterization.
+pub struct Checker {
+    inverse_scale: f32,
+    even: SharedTexture,
+    odd: SharedTexture,
+}
+
+impl Checker {
+    /// 'scale' is the edge length of a single cube.
+    pub fn new(scale: f32, even: SharedTexture, odd: SharedTexture) -> Self {
+        Self {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f32, v: f32, point: Point3) -> Color {
+        let x = (self.inverse_scale * point.x).floor() as i64;
+        let y = (self.inverse_scale * point.y).floor() as i64;
+        let z = (self.inverse_scale * point.z).floor() as i64;
+
+        match (x + y + z) % 2 == 0 {
+            true => self.even.value(u, v, point),
+            false => self.odd.value(u, v, point),
+        }
+    }
+}
+
+/// Checker pattern in texture space, with the given number of squares along
+/// u and v.
+pub struct UvChecker {
+    columns: f32,
+    rows: f32,
+    even: SharedTexture,
+    odd: SharedTexture,
+}
+
+impl UvChecker {
+    pub fn new(columns: u32, rows: u32, even: SharedTexture, odd: SharedTexture) -> Self {
+        Self {
+            columns: columns as f32,
+            rows: rows as f32,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u: f32, v: f32, point: Point3) -> Color {
+        let column = (u * self.columns).floor() as i64;
+        let row = (v * self.rows).floor() as i64;
+
+        match (column + row) % 2 == 0 {
+            true => self.even.value(u, v, point),
+            false => self.odd.value(u, v, point),
+        }
+    }
+}
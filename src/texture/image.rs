@@ -0,0 +1,143 @@
+use super::Texture;
+
+use crate::reading::{self, ImageError};
+use crate::{Color, Point3};
+
+use std::path::Path;
+
+/// How colors between the pixel centers are found.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// Color of the closest pixel
+    Nearest,
+    /// Linear interpolation of the four closest pixels
+    #[default]
+    Bilinear,
+}
+
+/// What happens to texture coordinates outside of 0..=1.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    /// Tiles the image
+    #[default]
+    Repeat,
+    /// Extends the edge pixels
+    Clamp,
+}
+
+/// Texture mapping the unit square of (u, v) onto an image, with v pointing
+/// up.
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+    filter: Filter,
+    wrap: Wrap,
+}
+
+impl ImageTexture {
+    /// 'pixels' holds the rows from top to bottom, as linear colors.
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "image texture of {width}x{height} pixels needs as many colors"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+            filter: Filter::default(),
+            wrap: Wrap::default(),
+        }
+    }
+
+    /// Loads a PPM or PNG file holding sRGB colors, like photos and most
+    /// painted textures, and converts them to linear colors. HDR and PFM
+    /// files are linear already and used as they are.
+    pub fn load(path: &Path) -> Result<Self, ImageError> {
+        let image = reading::read_image(path)?;
+        if image.is_linear {
+            return Ok(Self::new(image.width, image.height, image.pixels));
+        }
+
+        let pixels = image.pixels.into_iter().map(srgb_to_linear).collect();
+        Ok(Self::new(image.width, image.height, pixels))
+    }
+
+    /// Loads an image file whose values are used as they are.
+    pub fn load_linear(path: &Path) -> Result<Self, ImageError> {
+        let image = reading::read_image(path)?;
+        Ok(Self::new(image.width, image.height, image.pixels))
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixel at the given column and row, which may lie outside the image.
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let wrap = |coordinate: i64, size: u32| match self.wrap {
+            Wrap::Repeat => coordinate.rem_euclid(size as i64) as usize,
+            Wrap::Clamp => coordinate.clamp(0, size as i64 - 1) as usize,
+        };
+        let x = wrap(x, self.width);
+        let y = wrap(y, self.height);
+        self.pixels[y * self.width as usize + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _point: Point3) -> Color {
+        if self.pixels.is_empty() {
+            // Cyan, to make missing texture data stand out
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Position in pixels, with the rows counted from the top
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+
+        match self.filter {
+            Filter::Nearest => self.pixel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Pixel centers lie at half coordinates
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let (x0, y0) = (x.floor(), y.floor());
+                let (tx, ty) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = (1.0 - tx) * self.pixel(x0, y0) + tx * self.pixel(x0 + 1, y0);
+                let bottom = (1.0 - tx) * self.pixel(x0, y0 + 1) + tx * self.pixel(x0 + 1, y0 + 1);
+                (1.0 - ty) * top + ty * bottom
+            }
+        }
+    }
+}
+
+/// Applies the inverse of the sRGB transfer function to every component.
+pub(crate) fn srgb_to_linear(color: Color) -> Color {
+    let component = |value: f32| {
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    Color::new(component(color.r), component(color.g), component(color.b))
+}
@@ -0,0 +1,182 @@
+use super::Texture;
+
+use crate::perlin::Perlin;
+use crate::{random, Color, Point3, Vec3};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::f32::consts::PI;
+
+const TURBULENCE_DEPTH: u32 = 7;
+const WORLEY_POINT_COUNT: usize = 256;
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+fn scaled(point: Point3, scale: f32) -> Point3 {
+    Point3::new(scale * point.x, scale * point.y, scale * point.z)
+}
+
+/// Blends between two colors by the turbulence, which looks like smoke or
+/// clouds.
+pub struct Turbulence {
+    noise: Perlin,
+    scale: f32,
+    low: Color,
+    high: Color,
+}
+
+impl Turbulence {
+    pub fn new(seed: u64, scale: f32, low: Color, high: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            low,
+            high,
+        }
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> Color {
+        let t = self
+            .noise
+            .turbulence(scaled(point, self.scale), TURBULENCE_DEPTH);
+        mix(self.low, self.high, t.clamp(0.0, 1.0))
+    }
+}
+
+/// Stripes along the z axis, distorted by turbulence into veins.
+pub struct Marble {
+    noise: Perlin,
+    scale: f32,
+    base: Color,
+    veins: Color,
+}
+
+impl Marble {
+    pub fn new(seed: u64, scale: f32, base: Color, veins: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            base,
+            veins,
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> Color {
+        let phase = self.scale * point.z + 10.0 * self.noise.turbulence(point, TURBULENCE_DEPTH);
+        let t = 0.5 * (1.0 + phase.sin());
+        mix(self.veins, self.base, t)
+    }
+}
+
+/// Growth rings around the y axis, made irregular by turbulence.
+pub struct Wood {
+    noise: Perlin,
+    /// Rings per unit of distance
+    scale: f32,
+    light: Color,
+    dark: Color,
+}
+
+impl Wood {
+    pub fn new(seed: u64, scale: f32, light: Color, dark: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> Color {
+        let distance = (point.x * point.x + point.z * point.z).sqrt();
+        let distortion = 0.4 * self.noise.turbulence(scaled(point, 2.0), TURBULENCE_DEPTH);
+        let rings = self.scale * distance + distortion;
+
+        // Each ring fades from light to dark
+        let t = 0.5 - 0.5 * (2.0 * PI * rings).cos();
+        mix(self.light, self.dark, t * t)
+    }
+}
+
+/// Cellular noise after Steven Worley: the distance to the nearest of a set
+/// of random points, one per unit cube.
+pub struct Worley {
+    points: Vec<Vec3>,
+    permutations: [[u8; WORLEY_POINT_COUNT]; 3],
+    scale: f32,
+    near: Color,
+    far: Color,
+}
+
+impl Worley {
+    /// 'scale' is the number of cells per unit of distance. 'near' is the
+    /// color at the points and 'far' the color for a distance of one cell.
+    pub fn new(seed: u64, scale: f32, near: Color, far: Color) -> Self {
+        let mut rng = random::generator(seed);
+
+        let points = (0..WORLEY_POINT_COUNT)
+            .map(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()))
+            .collect();
+        let permutations = [(); 3].map(|_| {
+            let mut permutation = [0; WORLEY_POINT_COUNT];
+            for (i, value) in permutation.iter_mut().enumerate() {
+                *value = i as u8;
+            }
+            permutation.shuffle(&mut rng);
+            permutation
+        });
+
+        Self {
+            points,
+            permutations,
+            scale,
+            near,
+            far,
+        }
+    }
+
+    /// Random point inside the cell (i, j, k), relative to its corner.
+    fn feature_point(&self, i: i64, j: i64, k: i64) -> Vec3 {
+        let [x, y, z] = &self.permutations;
+        let index = x[(i & 255) as usize] ^ y[(j & 255) as usize] ^ z[(k & 255) as usize];
+        self.points[index as usize]
+    }
+
+    fn nearest_distance(&self, point: Point3) -> f32 {
+        let cell = [point.x.floor(), point.y.floor(), point.z.floor()];
+        let [i, j, k] = cell.map(|coordinate| coordinate as i64);
+
+        // The nearest point can only be in this or one of the adjacent cells
+        let mut nearest = f32::INFINITY;
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let feature = self.feature_point(i + di, j + dj, k + dk);
+                    let corner = Point3::new(
+                        cell[0] + di as f32,
+                        cell[1] + dj as f32,
+                        cell[2] + dk as f32,
+                    );
+                    nearest = nearest.min((corner + feature).distance_squared(point));
+                }
+            }
+        }
+        nearest.sqrt()
+    }
+}
+
+impl Texture for Worley {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> Color {
+        let t = self.nearest_distance(scaled(point, self.scale));
+        mix(self.near, self.far, t.clamp(0.0, 1.0))
+    }
+}
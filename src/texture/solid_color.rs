@@ -0,0 +1,19 @@
+use super::Texture;
+
+use crate::{Color, Point3};
+
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _point: Point3) -> Color {
+        self.color
+    }
+}
@@ -0,0 +1,356 @@
+//! Affine transformations as 4x4 matrices, used to place instances of
+//! objects in the scene.
+
+use crate::aabb::{self, Aabb};
+use crate::interval::Interval;
+use crate::{Point3, Vec3};
+
+use std::ops::Mul;
+
+/// Row-major 4x4 matrix, applied to column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    pub rows: [[f32; 4]; 4],
+}
+
+pub const IDENTITY: Matrix4 = Matrix4::new([
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+]);
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        IDENTITY
+    }
+}
+
+impl Matrix4 {
+    pub const fn new(rows: [[f32; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub fn transposed(&self) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.rows[j][i];
+            }
+        }
+        Self::new(rows)
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting. Returns None for
+    /// singular matrices, e.g. a scaling by zero.
+    pub fn inverse(&self) -> Option<Self> {
+        // Double precision keeps the round trip to the identity tight
+        let mut a = self.rows.map(|row| row.map(f64::from));
+        let mut inverse = IDENTITY.rows.map(|row| row.map(f64::from));
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor = a[row][column];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+
+        Some(Self::new(inverse.map(|row| row.map(|value| value as f32))))
+    }
+
+    /// Applies the matrix to (x, y, z, w).
+    fn apply(&self, [x, y, z]: [f32; 3], w: f32) -> [f32; 3] {
+        let row = |i: usize| {
+            let r = self.rows[i];
+            r[0] * x + r[1] * y + r[2] * z + r[3] * w
+        };
+        [row(0), row(1), row(2)]
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut rows = [[0.0; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Self::new(rows)
+    }
+}
+
+/// An invertible affine transformation, stored together with its inverse.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    /// Returns None if the matrix can't be inverted.
+    pub fn from_matrix(matrix: Matrix4) -> Option<Self> {
+        let inverse = matrix.inverse()?;
+        Some(Self { matrix, inverse })
+    }
+
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let matrix = |offset: Vec3| {
+            Matrix4::new([
+                [1.0, 0.0, 0.0, offset.x],
+                [0.0, 1.0, 0.0, offset.y],
+                [0.0, 0.0, 1.0, offset.z],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+        };
+        Self {
+            matrix: matrix(offset),
+            inverse: matrix(-offset),
+        }
+    }
+
+    /// Scales by a factor per axis, none of which may be zero.
+    pub fn scaling(factors: Vec3) -> Self {
+        assert!(
+            factors.x != 0.0 && factors.y != 0.0 && factors.z != 0.0,
+            "can't scale by zero"
+        );
+        let matrix = |x: f32, y: f32, z: f32| {
+            Matrix4::new([
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+        };
+        Self {
+            matrix: matrix(factors.x, factors.y, factors.z),
+            inverse: matrix(1.0 / factors.x, 1.0 / factors.y, 1.0 / factors.z),
+        }
+    }
+
+    /// Counter clockwise rotation around 'axis' when looking against it.
+    pub fn rotation(axis: Vec3, degrees: f32) -> Self {
+        let Vec3 { x, y, z } = axis.normalized();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        let matrix = Matrix4::new([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        // Rotations are orthogonal, so the inverse is the transpose
+        Self {
+            matrix,
+            inverse: matrix.transposed(),
+        }
+    }
+
+    pub fn rotation_x(degrees: f32) -> Self {
+        Self::rotation(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotation_y(degrees: f32) -> Self {
+        Self::rotation(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotation_z(degrees: f32) -> Self {
+        Self::rotation(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    /// Applies this transform first and 'next' afterwards.
+    pub fn then(self, next: Transform) -> Self {
+        Self {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+
+    pub fn point(&self, point: Point3) -> Point3 {
+        let [x, y, z] = self.matrix.apply([point.x, point.y, point.z], 1.0);
+        Point3::new(x, y, z)
+    }
+
+    /// Transforms a direction, which unlike a point isn't translated.
+    pub fn vector(&self, vector: Vec3) -> Vec3 {
+        let [x, y, z] = self.matrix.apply([vector.x, vector.y, vector.z], 0.0);
+        Vec3::new(x, y, z)
+    }
+
+    /// Transforms a surface normal with the inverse transpose, so it stays
+    /// perpendicular to the surface under non-uniform scaling. The result
+    /// isn't normalized.
+    pub fn normal(&self, normal: Vec3) -> Vec3 {
+        let [x, y, z] = self
+            .inverse
+            .transposed()
+            .apply([normal.x, normal.y, normal.z], 0.0);
+        Vec3::new(x, y, z)
+    }
+
+    /// The smallest axis-aligned box around the transformed box. Each axis
+    /// of the result is the sum of the contributions of every input axis,
+    /// which also works for unbounded boxes.
+    pub fn bounding_box(&self, bounding_box: Aabb) -> Aabb {
+        if bounding_box.is_empty() {
+            return aabb::EMPTY;
+        }
+
+        let rows = self.matrix.rows;
+        let axis = |i: usize| {
+            let mut min = rows[i][3];
+            let mut max = rows[i][3];
+            for (j, &factor) in rows[i][..3].iter().enumerate() {
+                // Skipping zero factors avoids 0 * inf
+                if factor == 0.0 {
+                    continue;
+                }
+                let input = bounding_box.axis(j);
+                let (a, b) = (factor * input.min, factor * input.max);
+                min += a.min(b);
+                max += a.max(b);
+            }
+            Interval::new(min, max)
+        };
+
+        Aabb::new(axis(0), axis(1), axis(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composed() -> Transform {
+        Transform::scaling(Vec3::new(2.0, 0.5, -3.0))
+            .then(Transform::rotation(Vec3::new(1.0, 2.0, 3.0), 37.0))
+            .then(Transform::translation(Vec3::new(4.0, -5.0, 6.0)))
+    }
+
+    fn assert_identity(matrix: Matrix4) {
+        for (i, row) in matrix.rows.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                let expected = IDENTITY.rows[i][j];
+                assert!((value - expected).abs() < 1e-5, "{matrix:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn inverts_matrices() {
+        let matrix = composed().matrix();
+        let inverse = matrix.inverse().unwrap();
+        assert_identity(matrix * inverse);
+        assert_identity(inverse * matrix);
+
+        // The inverse that 'then' builds from the parts agrees
+        assert_identity(matrix * composed().inverse().matrix());
+
+        let flattening = Matrix4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [2.0, 3.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert!(flattening.inverse().is_none());
+        assert!(Transform::from_matrix(flattening).is_none());
+    }
+
+    #[test]
+    fn bounds_transformed_boxes() {
+        let transform = composed();
+        let min = Point3::new(-1.0, 0.5, 2.0);
+        let max = Point3::new(3.0, 2.0, 2.5);
+        let bounds = transform.bounding_box(Aabb::from_points(min, max));
+
+        let corners: Vec<_> = (0..8)
+            .map(|i| {
+                let pick = |bit: usize, low: f32, high: f32| match i & bit {
+                    0 => low,
+                    _ => high,
+                };
+                transform.point(Point3::new(
+                    pick(1, min.x, max.x),
+                    pick(2, min.y, max.y),
+                    pick(4, min.z, max.z),
+                ))
+            })
+            .collect();
+
+        for axis in 0..3 {
+            let extent = bounds.axis(axis);
+            let coordinates: Vec<f32> = corners
+                .iter()
+                .map(|corner| [corner.x, corner.y, corner.z][axis])
+                .collect();
+            for &coordinate in &coordinates {
+                assert!(extent.expanded(2e-4).contains(coordinate), "{extent:?}");
+            }
+            // And it's the smallest such box
+            let lowest = coordinates.iter().copied().fold(f32::INFINITY, f32::min);
+            let highest = coordinates
+                .iter()
+                .copied()
+                .fold(f32::NEG_INFINITY, f32::max);
+            assert!((extent.min - lowest).abs() < 1e-4);
+            assert!((extent.max - highest).abs() < 1e-4);
+        }
+
+        assert!(transform.bounding_box(aabb::EMPTY).is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::aabb::Aabb;
+use crate::plane;
+use crate::random;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedMaterial, Vec3};
+
+use std::sync::Arc;
+
+/// Thickness given to the bounding boxes of triangles and other flat objects
+/// lying in an axis plane.
+pub const BOUNDING_BOX_PADDING: f32 = 1e-4;
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: [(f32, f32); 3],
+    material: SharedMaterial,
+    bounding_box: Aabb,
+}
+
+impl Triangle {
+    /// Creates a flat shaded triangle. The vertices are expected in counter
+    /// clockwise order when looking at the front face.
+    pub fn new(a: Point3, b: Point3, c: Point3, material: SharedMaterial) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            material,
+            bounding_box: bounding_box(&[a, b, c]),
+        }
+    }
+
+    /// Per-vertex normals that get interpolated across the triangle for
+    /// smooth shading.
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    pub fn vertices(&self) -> [Point3; 3] {
+        self.vertices
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let Some((t, b1, b2)) = intersect(ray, &self.vertices, allowed_t) else {
+            return false;
+        };
+
+        fill_hit_record(
+            hit_rec,
+            ray,
+            (t, b1, b2),
+            &self.vertices,
+            self.normals.as_ref(),
+            &self.uvs,
+            &self.material,
+        );
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        pdf_value(&self.vertices, origin, direction)
+    }
+
+    fn random_direction(&self, origin: Point3) -> Vec3 {
+        random_direction(&self.vertices, origin)
+    }
+}
+
+pub(crate) fn bounding_box(vertices: &[Point3; 3]) -> Aabb {
+    let [a, b, c] = *vertices;
+    Aabb::enclosing(Aabb::from_points(a, b), Aabb::from_points(c, c)).padded(BOUNDING_BOX_PADDING)
+}
+
+/// Möller–Trumbore intersection. Returns the ray parameter and the
+/// barycentric coordinates of the second and third vertex.
+pub(crate) fn intersect(
+    ray: &Ray,
+    vertices: &[Point3; 3],
+    allowed_t: Interval,
+) -> Option<(f32, f32, f32)> {
+    let [p0, p1, p2] = *vertices;
+    let edge_1 = p1 - p0;
+    let edge_2 = p2 - p0;
+
+    let p = Vec3::cross(ray.direction(), edge_2);
+    let determinant = Vec3::dot(edge_1, p);
+    // The ray is parallel to the triangle
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+
+    let s = ray.origin() - p0;
+    let b1 = Vec3::dot(s, p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = Vec3::cross(s, edge_1);
+    let b2 = Vec3::dot(ray.direction(), q) * inverse_determinant;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = Vec3::dot(edge_2, q) * inverse_determinant;
+    match allowed_t.surrounds(t) {
+        true => Some((t, b1, b2)),
+        false => None,
+    }
+}
+
+/// Fills the hit record from the result of 'intersect', interpolating the
+/// per-vertex normals and UVs with the barycentric coordinates.
+pub(crate) fn fill_hit_record(
+    hit_rec: &mut HitRecord,
+    ray: &Ray,
+    (t, b1, b2): (f32, f32, f32),
+    vertices: &[Point3; 3],
+    normals: Option<&[Vec3; 3]>,
+    uvs: &[(f32, f32); 3],
+    material: &SharedMaterial,
+) {
+    let b0 = 1.0 - b1 - b2;
+    let [p0, p1, p2] = *vertices;
+
+    hit_rec.t = t;
+    hit_rec.point = ray.at(t);
+
+    // Which side got hit is decided by the geometric normal, the shading
+    // normal only bends the normal on that side
+    let geometric_normal = Vec3::cross(p1 - p0, p2 - p0).normalized();
+    hit_rec.set_face_normal(ray, geometric_normal);
+    if let Some([n0, n1, n2]) = normals {
+        let shading_normal = (b0 * *n0 + b1 * *n1 + b2 * *n2).normalized();
+        hit_rec.normal = match hit_rec.front_face {
+            true => shading_normal,
+            false => -shading_normal,
+        };
+    }
+
+    let [uv0, uv1, uv2] = *uvs;
+    hit_rec.u = b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0;
+    hit_rec.v = b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1;
+    hit_rec.material = Some(Arc::clone(material));
+}
+
+/// Density per solid angle of 'random_direction' picking 'direction'.
+pub(crate) fn pdf_value(vertices: &[Point3; 3], origin: Point3, direction: Vec3) -> f32 {
+    let ray = Ray::new(origin, direction);
+    let Some((t, _, _)) = intersect(&ray, vertices, Interval::new(0.001, f32::INFINITY)) else {
+        return 0.0;
+    };
+
+    let [p0, p1, p2] = *vertices;
+    let n = Vec3::cross(p1 - p0, p2 - p0);
+    plane::solid_angle_pdf(direction, t, n.normalized(), 0.5 * n.length())
+}
+
+/// Direction from 'origin' to a point picked uniformly on the triangle.
+pub(crate) fn random_direction(vertices: &[Point3; 3], origin: Point3) -> Vec3 {
+    let [p0, p1, p2] = *vertices;
+    // Folding the unit square along its diagonal keeps the points uniform
+    let (mut b1, mut b2) = (random::random(), random::random());
+    if b1 + b2 > 1.0 {
+        (b1, b2) = (1.0 - b1, 1.0 - b2);
+    }
+    p0 + b1 * (p1 - p0) + b2 * (p2 - p0) - origin
+}
@@ -0,0 +1,171 @@
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
+use crate::hittable_list::HittableList;
+use crate::triangle;
+use crate::{HitRecord, Hittable, Interval, Point3, Ray, SharedHittable, SharedMaterial, Vec3};
+
+use std::sync::Arc;
+
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f32, f32)>>,
+    indices: Vec<[u32; 3]>,
+    material: SharedMaterial,
+}
+
+/// Triangles sharing one set of vertex buffers. Every vertex attribute is
+/// addressed by the same index, so positions, normals and UVs have to be
+/// of equal length.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+}
+
+impl TriangleMesh {
+    /// 'indices' holds three vertex indices per triangle, in counter clockwise
+    /// order when looking at the front face.
+    pub fn new(positions: Vec<Point3>, indices: Vec<[u32; 3]>, material: SharedMaterial) -> Self {
+        let vertex_count = positions.len();
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < vertex_count),
+            "triangle mesh index out of bounds, the mesh has {vertex_count} vertices"
+        );
+
+        Self {
+            data: Arc::new(MeshData {
+                positions,
+                normals: None,
+                uvs: None,
+                indices,
+                material,
+            }),
+        }
+    }
+
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Self {
+        self.data_mut().normals = Some(normals);
+        self.check_attribute_length("normals", |data| data.normals.as_ref().map(Vec::len));
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<(f32, f32)>) -> Self {
+        self.data_mut().uvs = Some(uvs);
+        self.check_attribute_length("uvs", |data| data.uvs.as_ref().map(Vec::len));
+        self
+    }
+
+    pub fn with_material(mut self, material: SharedMaterial) -> Self {
+        self.data_mut().material = material;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.indices.is_empty()
+    }
+
+    /// Returns every triangle as its own hittable. They only reference the
+    /// shared buffers, so they can be added to a HittableList cheaply and be
+    /// sorted into the same BVH as the rest of the scene.
+    pub fn triangles(&self) -> impl Iterator<Item = SharedHittable> + '_ {
+        (0..self.data.indices.len()).map(|index| {
+            let triangle = MeshTriangle::new(Arc::clone(&self.data), index);
+            Arc::new(triangle) as SharedHittable
+        })
+    }
+
+    /// Builds a BVH over the triangles, so the mesh can be used as a single
+    /// hittable.
+    pub fn into_bvh(self) -> BvhNode {
+        BvhNode::new(HittableList::new(self.triangles().collect()))
+    }
+
+    fn data_mut(&mut self) -> &mut MeshData {
+        Arc::get_mut(&mut self.data).expect("mesh buffers can't change once triangles exist")
+    }
+
+    fn check_attribute_length(&self, name: &str, length: impl Fn(&MeshData) -> Option<usize>) {
+        let expected = self.data.positions.len();
+        let actual = length(&self.data).unwrap_or(expected);
+        assert_eq!(
+            actual, expected,
+            "triangle mesh has {expected} positions but {actual} {name}"
+        );
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+    bounding_box: Aabb,
+}
+
+impl MeshTriangle {
+    fn new(mesh: Arc<MeshData>, index: usize) -> Self {
+        let mut triangle = Self {
+            mesh,
+            index,
+            bounding_box: Aabb::default(),
+        };
+        triangle.bounding_box = triangle::bounding_box(&triangle.vertices());
+        triangle
+    }
+
+    fn indices(&self) -> [usize; 3] {
+        self.mesh.indices[self.index].map(|i| i as usize)
+    }
+
+    fn vertices(&self) -> [Point3; 3] {
+        self.indices().map(|i| self.mesh.positions[i])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit_mutating(&self, ray: &Ray, allowed_t: Interval, hit_rec: &mut HitRecord) -> bool {
+        let vertices = self.vertices();
+        let Some(hit) = triangle::intersect(ray, &vertices, allowed_t) else {
+            return false;
+        };
+
+        let indices = self.indices();
+        let normals = self
+            .mesh
+            .normals
+            .as_ref()
+            .map(|normals| indices.map(|i| normals[i]));
+        let uvs = match &self.mesh.uvs {
+            Some(uvs) => indices.map(|i| uvs[i]),
+            None => [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+        };
+
+        triangle::fill_hit_record(
+            hit_rec,
+            ray,
+            hit,
+            &vertices,
+            normals.as_ref(),
+            &uvs,
+            &self.mesh.material,
+        );
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        triangle::pdf_value(&self.vertices(), origin, direction)
+    }
+
+    fn random_direction(&self, origin: Point3) -> Vec3 {
+        triangle::random_direction(&self.vertices(), origin)
+    }
+}
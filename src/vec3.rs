@@ -121,6 +121,15 @@ impl Vec3 {
         }
     }
 
+    /// Direction on the hemisphere around +z, with a density proportional
+    /// to the cosine towards +z.
+    pub fn random_cosine_direction() -> Self {
+        let phi = 2.0 * std::f32::consts::PI * random::random();
+        let r2 = random::random();
+        let radius = r2.sqrt();
+        Self::new(radius * phi.cos(), radius * phi.sin(), (1.0 - r2).sqrt())
+    }
+
     pub fn random_in_unit_disk() -> Self {
         let random_in_unit_square = || {
             Self::new(
@@ -0,0 +1,275 @@
+//! Density grids for heterogeneous volumes, loaded from a raw voxel file
+//! described by a small text header:
+//!
+//! ```text
+//! # Comments start with '#'
+//! size 64 32 64
+//! format f32
+//! data smoke.raw
+//! ```
+//!
+//! 'size' is the number of voxels along x, y and z. The data file, relative
+//! to the header, holds the voxels with x changing fastest and z slowest, as
+//! 'u8', 'u16' (little endian, both scaled to 0..=1) or 'f32' (little
+//! endian) values.
+
+use crate::Point3;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct VoxelGrid {
+    size: [usize; 3],
+    densities: Vec<f32>,
+    max_density: f32,
+}
+
+impl VoxelGrid {
+    /// 'densities' holds the voxels with x changing fastest and z slowest.
+    pub fn new(size: [usize; 3], densities: Vec<f32>) -> Self {
+        assert_eq!(
+            Some(densities.len()),
+            voxel_count(size),
+            "voxel grid of size {size:?} needs as many densities"
+        );
+        assert!(
+            densities.iter().all(|&density| density >= 0.0),
+            "densities can't be negative"
+        );
+
+        let max_density = densities.iter().copied().fold(0.0, f32::max);
+        Self {
+            size,
+            densities,
+            max_density,
+        }
+    }
+
+    /// Loads the grid described by the header file at 'path'.
+    pub fn load(path: &Path) -> Result<Self, VoxelError> {
+        let header = fs::read_to_string(path).map_err(|error| VoxelError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let invalid = |message: String| VoxelError::Invalid {
+            path: path.to_path_buf(),
+            message,
+        };
+
+        let header = Header::parse(&header).map_err(invalid)?;
+        let too_large = || {
+            let [x, y, z] = header.size;
+            invalid(format!("a grid of {x}x{y}x{z} voxels is too large"))
+        };
+        let count = voxel_count(header.size).ok_or_else(too_large)?;
+        let expected = count
+            .checked_mul(header.format.bytes_per_voxel())
+            .ok_or_else(too_large)?;
+
+        let data_path = path.parent().unwrap_or(Path::new("")).join(&header.data);
+        let data = fs::read(&data_path).map_err(|error| VoxelError::Io {
+            path: data_path.clone(),
+            error,
+        })?;
+
+        if data.len() != expected {
+            return Err(invalid(format!(
+                "{} has {} bytes, but {} voxels of '{}' need {}",
+                data_path.display(),
+                data.len(),
+                count,
+                header.format.name(),
+                expected
+            )));
+        }
+
+        let densities: Vec<f32> = match header.format {
+            Format::U8 => data.iter().map(|&byte| byte as f32 / 255.0).collect(),
+            Format::U16 => data
+                .chunks_exact(2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0)
+                .collect(),
+            Format::F32 => data
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                .collect(),
+        };
+        if let Some(index) = densities
+            .iter()
+            .position(|density| !density.is_finite() || *density < 0.0)
+        {
+            return Err(invalid(format!(
+                "voxel {index} has the invalid density {}",
+                densities[index]
+            )));
+        }
+
+        Ok(Self::new(header.size, densities))
+    }
+
+    pub fn size(&self) -> [usize; 3] {
+        self.size
+    }
+
+    pub fn max_density(&self) -> f32 {
+        self.max_density
+    }
+
+    /// Trilinearly interpolated density at a point of the unit cube that the
+    /// grid spans. Outside of it, the density is zero.
+    pub fn density(&self, point: Point3) -> f32 {
+        let coordinates = [point.x, point.y, point.z];
+        if coordinates.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return 0.0;
+        }
+
+        // Voxel values sit at the voxel centers
+        let mut lower = [0; 3];
+        let mut weights = [0.0; 3];
+        for axis in 0..3 {
+            let position = coordinates[axis] * self.size[axis] as f32 - 0.5;
+            let floor = position.floor();
+            lower[axis] = floor as i64;
+            weights[axis] = position - floor;
+        }
+
+        let mut density = 0.0;
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut index = [0; 3];
+            for axis in 0..3 {
+                let upper = (corner >> axis) & 1 == 1;
+                weight *= match upper {
+                    true => weights[axis],
+                    false => 1.0 - weights[axis],
+                };
+                let voxel = lower[axis] + upper as i64;
+                index[axis] = voxel.clamp(0, self.size[axis] as i64 - 1) as usize;
+            }
+            density += weight * self.voxel(index);
+        }
+        density
+    }
+
+    fn voxel(&self, [x, y, z]: [usize; 3]) -> f32 {
+        let [width, height, _] = self.size;
+        self.densities[(z * height + y) * width + x]
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    U8,
+    U16,
+    F32,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::F32 => "f32",
+        }
+    }
+
+    fn bytes_per_voxel(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::F32 => 4,
+        }
+    }
+}
+
+struct Header {
+    size: [usize; 3],
+    format: Format,
+    data: PathBuf,
+}
+
+impl Header {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut format = None;
+        let mut data = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.trim().split_once(char::is_whitespace) else {
+                if !line.trim().is_empty() {
+                    return Err(format!(
+                        "line {line_number}: '{}' has no value",
+                        line.trim()
+                    ));
+                }
+                continue;
+            };
+            let value = value.trim();
+
+            match key {
+                "size" => {
+                    let numbers: Vec<usize> = value
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| format!("line {line_number}: invalid size '{value}'"))?;
+                    match numbers[..] {
+                        [x, y, z] if x > 0 && y > 0 && z > 0 => size = Some([x, y, z]),
+                        _ => {
+                            return Err(format!(
+                                "line {line_number}: 'size' needs three positive whole numbers"
+                            ))
+                        }
+                    }
+                }
+                "format" => {
+                    format = Some(match value {
+                        "u8" => Format::U8,
+                        "u16" => Format::U16,
+                        "f32" => Format::F32,
+                        other => {
+                            return Err(format!(
+                            "line {line_number}: unknown format '{other}', expected u8, u16 or f32"
+                        ))
+                        }
+                    })
+                }
+                "data" => data = Some(PathBuf::from(value)),
+                other => return Err(format!("line {line_number}: unknown key '{other}'")),
+            }
+        }
+
+        Ok(Self {
+            size: size.ok_or("header is missing the 'size'")?,
+            format: format.ok_or("header is missing the 'format'")?,
+            data: data.ok_or("header is missing the 'data' path")?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum VoxelError {
+    Io { path: PathBuf, error: io::Error },
+    Invalid { path: PathBuf, message: String },
+}
+
+impl fmt::Display for VoxelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            Self::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for VoxelError {}
+
+/// Number of voxels of a grid with the given size, None if it overflows.
+fn voxel_count(size: [usize; 3]) -> Option<usize> {
+    size.iter()
+        .try_fold(1usize, |count, &length| count.checked_mul(length))
+}
@@ -1,49 +1,158 @@
+pub(crate) mod hdr;
+pub(crate) mod pfm;
+pub(crate) mod png;
+pub(crate) mod zlib;
+
 const FILE_TYPE: &str = "P3";
 
 use crate::color::{Color, MAX_VALUE};
 use crate::renderer::ImageBuffer;
 
-use std::io::{self, BufWriter, Stdout, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 use std::time::Instant;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// ASCII PPM (P3)
+    Ppm,
+    /// 8-bit RGB PNG
+    Png,
+    /// 8-bit RGBA PNG with an opaque alpha channel
+    PngRgba,
+    /// Linear 32-bit float Portable FloatMap
+    Pfm,
+    /// Linear Radiance RGBE
+    Hdr,
+}
+
+impl ImageFormat {
+    pub const NAMES: [&'static str; 5] = ["ppm", "png", "png-rgba", "pfm", "hdr"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ppm" => Some(Self::Ppm),
+            "png" => Some(Self::Png),
+            "png-rgba" => Some(Self::PngRgba),
+            "pfm" => Some(Self::Pfm),
+            "hdr" => Some(Self::Hdr),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the file extension of 'path'.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(Self::Ppm),
+            "png" => Some(Self::Png),
+            "pfm" => Some(Self::Pfm),
+            "hdr" => Some(Self::Hdr),
+            _ => None,
+        }
+    }
+}
+
 pub struct FileWriter {
-    stdout: BufWriter<Stdout>,
+    out: BufWriter<Box<dyn Write>>,
+    format: ImageFormat,
+    image_width: u32,
+    image_height: u32,
 }
 
 impl FileWriter {
-    pub fn new(image_width: u32, image_height: u32) -> io::Result<Self> {
-        let mut stdout = BufWriter::new(io::stdout());
+    /// Creates a writer for the file at 'path', or for stdout if no path is
+    /// given.
+    pub fn new(
+        format: ImageFormat,
+        path: Option<&Path>,
+        image_width: u32,
+        image_height: u32,
+    ) -> io::Result<Self> {
+        let out: Box<dyn Write> = match path {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+        let mut out = BufWriter::new(out);
 
-        // Write metadata
-        write!(
-            stdout,
-            "{}\n{} {}\n{}\n",
-            FILE_TYPE, image_width, image_height, MAX_VALUE
-        )?;
+        if format == ImageFormat::Ppm {
+            // Write metadata
+            write!(
+                out,
+                "{}\n{} {}\n{}\n",
+                FILE_TYPE, image_width, image_height, MAX_VALUE
+            )?;
+        }
 
-        Ok(Self { stdout })
+        Ok(Self {
+            out,
+            format,
+            image_width,
+            image_height,
+        })
     }
 
     pub fn write_image(&mut self, image: ImageBuffer, samples_per_pixel: u32) -> io::Result<()> {
-        for pixel_color in image.get_buffer().iter() {
-            self.write_pixel(*pixel_color, samples_per_pixel)?;
+        match self.format {
+            ImageFormat::Ppm => {
+                for pixel_color in image.get_buffer().iter() {
+                    self.write_pixel(*pixel_color, samples_per_pixel)?;
+                }
+                Ok(())
+            }
+            ImageFormat::Png => self.write_png(&image, samples_per_pixel, 3),
+            ImageFormat::PngRgba => self.write_png(&image, samples_per_pixel, 4),
+            ImageFormat::Pfm | ImageFormat::Hdr => self.write_linear(&image, samples_per_pixel),
         }
-
-        Ok(())
     }
 
     fn write_pixel(&mut self, pixel_color: Color, samples_per_pixel: u32) -> io::Result<()> {
-        write!(
-            self.stdout,
-            "{}",
-            pixel_color.pixel_format(samples_per_pixel)
+        write!(self.out, "{}", pixel_color.pixel_format(samples_per_pixel))
+    }
+
+    fn write_png(
+        &mut self,
+        image: &ImageBuffer,
+        samples_per_pixel: u32,
+        channels: usize,
+    ) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(image.get_buffer().len() * channels);
+        for pixel_color in image.get_buffer().iter() {
+            bytes.extend_from_slice(&pixel_color.to_rgb8(samples_per_pixel));
+            if channels == 4 {
+                bytes.push(MAX_VALUE as u8);
+            }
+        }
+
+        png::write(
+            &mut self.out,
+            self.image_width,
+            self.image_height,
+            channels,
+            &bytes,
         )
     }
+
+    fn write_linear(&mut self, image: &ImageBuffer, samples_per_pixel: u32) -> io::Result<()> {
+        let pixels: Vec<Color> = image
+            .get_buffer()
+            .iter()
+            .map(|pixel_color| pixel_color.to_linear(samples_per_pixel))
+            .collect();
+
+        match self.format {
+            ImageFormat::Pfm => {
+                pfm::write(&mut self.out, self.image_width, self.image_height, &pixels)
+            }
+            _ => hdr::write(&mut self.out, self.image_width, self.image_height, &pixels),
+        }
+    }
 }
 
 impl Drop for FileWriter {
     fn drop(&mut self) {
-        let result = self.stdout.flush();
+        let result = self.out.flush();
         if let Err(error) = result {
             eprintln!("Warning: an error occured while flushing FileWriter: {error}");
         }
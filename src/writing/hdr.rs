@@ -0,0 +1,142 @@
+use crate::Color;
+
+use std::io::{self, Write};
+
+/// Scanlines outside of this width range can't be run length encoded.
+const MIN_RLE_WIDTH: u32 = 8;
+const MAX_RLE_WIDTH: u32 = 0x7fff;
+
+/// Longest run or literal sequence a single RLE packet can hold.
+const MAX_PACKET_LENGTH: usize = 127;
+
+/// Writes a Radiance RGBE (.hdr) image. 'pixels' holds linear colors with the
+/// rows from top to bottom. Scanlines are stored with the adaptive run length
+/// encoding where possible.
+pub fn write(out: &mut impl Write, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let mut scanline: Vec<[u8; 4]> = Vec::with_capacity(width as usize);
+    for row in pixels.chunks(width as usize) {
+        scanline.clear();
+        scanline.extend(row.iter().map(|color| to_rgbe(*color)));
+
+        if (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width) {
+            write_rle_scanline(out, &scanline)?;
+        } else {
+            for pixel in &scanline {
+                out.write_all(pixel)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Shared exponent encoding: the mantissas of all three components are
+/// scaled by the exponent of the largest one. Infinite and too bright
+/// components saturate at the largest value RGBE can hold, NaN becomes 0.
+fn to_rgbe(color: Color) -> [u8; 4] {
+    let largest = 255.0 / 256.0 * 2f32.powi(127);
+    let sanitize = |component: f32| match component.is_nan() {
+        true => 0.0,
+        false => component.clamp(0.0, largest),
+    };
+    let (r, g, b) = (sanitize(color.r), sanitize(color.g), sanitize(color.b));
+
+    let max = r.max(g).max(b);
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    let (mantissa, exponent) = frexp(max);
+    let scale = mantissa * 256.0 / max;
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+/// Splits 'x' into a mantissa in [0.5, 1) and a power of two exponent.
+fn frexp(x: f32) -> (f32, i32) {
+    let exponent = x.log2().floor() as i32 + 1;
+    let mantissa = x / 2f32.powi(exponent);
+    // Correct rounding errors of the logarithm
+    match mantissa {
+        m if m >= 1.0 => (m / 2.0, exponent + 1),
+        m if m < 0.5 => (m * 2.0, exponent - 1),
+        m => (m, exponent),
+    }
+}
+
+fn write_rle_scanline(out: &mut impl Write, scanline: &[[u8; 4]]) -> io::Result<()> {
+    let width = scanline.len();
+    out.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+
+    // Every component is encoded separately
+    let mut component = Vec::with_capacity(width);
+    for channel in 0..4 {
+        component.clear();
+        component.extend(scanline.iter().map(|pixel| pixel[channel]));
+        write_rle_component(out, &component)?;
+    }
+
+    Ok(())
+}
+
+fn write_rle_component(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let mut position = 0;
+    while position < data.len() {
+        let run = run_length(&data[position..]);
+        if run > 2 {
+            out.write_all(&[128 + run as u8, data[position]])?;
+            position += run;
+            continue;
+        }
+
+        // Collect literals until the next run worth encoding
+        let start = position;
+        while position < data.len()
+            && position - start < MAX_PACKET_LENGTH
+            && run_length(&data[position..]) <= 2
+        {
+            position += 1;
+        }
+        out.write_all(&[(position - start) as u8])?;
+        out.write_all(&data[start..position])?;
+    }
+
+    Ok(())
+}
+
+fn run_length(data: &[u8]) -> usize {
+    data.iter()
+        .take(MAX_PACKET_LENGTH)
+        .take_while(|&&value| value == data[0])
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_with_a_shared_exponent() {
+        assert_eq!(to_rgbe(Color::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe(Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn saturates_non_finite_components() {
+        assert_eq!(
+            to_rgbe(Color::new(f32::INFINITY, f32::NAN, 1.0)),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(to_rgbe(Color::new(f32::MAX, 0.0, 0.0)), [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,20 @@
+use crate::Color;
+
+use std::io::{self, Write};
+
+/// Writes a color Portable FloatMap. 'pixels' holds linear colors with the
+/// rows from top to bottom. PFM stores the rows from bottom to top, and the
+/// negative scale in the header marks the floats as little endian.
+pub fn write(out: &mut impl Write, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+
+    for row in pixels.chunks(width as usize).rev() {
+        for color in row {
+            out.write_all(&color.r.to_le_bytes())?;
+            out.write_all(&color.g.to_le_bytes())?;
+            out.write_all(&color.b.to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use super::zlib;
+
+use std::io::{self, Write};
+
+pub(crate) const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const COLOR_TYPE_RGB: u8 = 2;
+const COLOR_TYPE_RGBA: u8 = 6;
+
+/// Writes an 8-bit PNG. 'pixels' holds the rows from top to bottom, with
+/// 'channels' bytes per pixel (3 for RGB, 4 for RGBA).
+pub fn write(
+    out: &mut impl Write,
+    width: u32,
+    height: u32,
+    channels: usize,
+    pixels: &[u8],
+) -> io::Result<()> {
+    let color_type = match channels {
+        3 => COLOR_TYPE_RGB,
+        4 => COLOR_TYPE_RGBA,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("PNG images can't have {channels} channels"),
+            ))
+        }
+    };
+
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth, color type, compression, filter and interlace method
+    header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    let filtered = filter_rows(pixels, width as usize * channels, channels);
+    write_chunk(out, b"IDAT", &zlib::compress(&filtered))?;
+
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// Prefixes every row with a filter type, choosing for each row the filter
+/// with the smallest sum of absolute differences, which tends to compress
+/// best.
+fn filter_rows(pixels: &[u8], row_length: usize, bytes_per_pixel: usize) -> Vec<u8> {
+    let mut filtered = Vec::with_capacity(pixels.len() + pixels.len() / row_length.max(1));
+    let empty_row = vec![0; row_length];
+    let mut candidate = vec![0; row_length];
+    let mut best = vec![0; row_length];
+
+    for (i, row) in pixels.chunks(row_length).enumerate() {
+        let previous = match i {
+            0 => &empty_row[..],
+            _ => &pixels[(i - 1) * row_length..i * row_length],
+        };
+
+        let mut best_filter = 0;
+        let mut best_score = u64::MAX;
+        for filter in 0..5 {
+            apply_filter(filter, row, previous, bytes_per_pixel, &mut candidate);
+            let score = candidate
+                .iter()
+                .map(|&byte| (byte as i8).unsigned_abs() as u64)
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+
+        filtered.push(best_filter);
+        filtered.extend_from_slice(&best);
+    }
+
+    filtered
+}
+
+fn apply_filter(filter: u8, row: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) {
+    for i in 0..row.len() {
+        let left = if i >= bpp { row[i - bpp] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            _ => paeth(left, up, up_left),
+        };
+        out[i] = row[i].wrapping_sub(predictor);
+    }
+}
+
+pub(crate) fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+pub(crate) fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let crc = bytes.fold(0xffff_ffff, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+    crc ^ 0xffff_ffff
+}
@@ -0,0 +1,209 @@
+//! Minimal zlib (RFC 1950) encoder. The data is compressed into a single
+//! deflate (RFC 1951) block using the fixed Huffman codes, with LZ77 matches
+//! found through hash chains.
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 64;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+
+    // CMF: deflate with a 32K window, FLG: no dictionary, default level.
+    // Together they have to be a multiple of 31.
+    writer.bytes.extend_from_slice(&[0x78, 0x9c]);
+
+    writer.write_bits(1, 1); // BFINAL
+    writer.write_bits(0b01, 2); // BTYPE: fixed Huffman codes
+    deflate(data, &mut writer);
+    write_literal_or_length(&mut writer, 256); // end of block
+    writer.flush();
+
+    writer.bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    writer.bytes
+}
+
+fn deflate(data: &[u8], writer: &mut BitWriter) {
+    let mut matcher = Matcher::new(data);
+
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = matcher.find_match(position);
+
+        if length >= MIN_MATCH {
+            write_match(writer, length, distance);
+            for p in position..position + length {
+                matcher.insert(p);
+            }
+            position += length;
+        } else {
+            write_literal_or_length(writer, data[position] as u16);
+            matcher.insert(position);
+            position += 1;
+        }
+    }
+}
+
+/// Hash chains over the 3 byte prefixes of the positions seen so far.
+struct Matcher<'a> {
+    data: &'a [u8],
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            previous: vec![usize::MAX; WINDOW_SIZE],
+        }
+    }
+
+    fn hash(&self, position: usize) -> usize {
+        let bytes = &self.data[position..position + MIN_MATCH];
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, position: usize) {
+        if position + MIN_MATCH <= self.data.len() {
+            let hash = self.hash(position);
+            self.previous[position % WINDOW_SIZE] = self.head[hash];
+            self.head[hash] = position;
+        }
+    }
+
+    /// Returns the length and distance of the longest match for the bytes at
+    /// 'position' within the window, or a length of zero if there is none.
+    fn find_match(&self, position: usize) -> (usize, usize) {
+        if position + MIN_MATCH > self.data.len() {
+            return (0, 0);
+        }
+
+        let max_length = MAX_MATCH.min(self.data.len() - position);
+        let mut best = (0, 0);
+        let mut candidate = self.head[self.hash(position)];
+
+        for _ in 0..MAX_CHAIN {
+            if candidate == usize::MAX || position - candidate >= WINDOW_SIZE {
+                break;
+            }
+
+            let length = self.data[candidate..]
+                .iter()
+                .zip(&self.data[position..position + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, position - candidate);
+                if length == max_length {
+                    break;
+                }
+            }
+
+            let next = self.previous[candidate % WINDOW_SIZE];
+            // The slot may have been reused by a newer position
+            if next == usize::MAX || next >= candidate {
+                break;
+            }
+            candidate = next;
+        }
+
+        best
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let length_code = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    write_literal_or_length(writer, 257 + length_code as u16);
+    writer.write_bits(
+        (length - LENGTH_BASE[length_code] as usize) as u32,
+        LENGTH_EXTRA_BITS[length_code],
+    );
+
+    let distance_code = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    writer.write_huffman(distance_code as u32, 5);
+    writer.write_bits(
+        (distance - DISTANCE_BASE[distance_code] as usize) as u32,
+        DISTANCE_EXTRA_BITS[distance_code],
+    );
+}
+
+/// Writes a symbol of the literal/length alphabet with its fixed code.
+fn write_literal_or_length(writer: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => writer.write_huffman(0b0011_0000 + symbol, 8),
+        144..=255 => writer.write_huffman(0b1_1001_0000 + symbol - 144, 9),
+        256..=279 => writer.write_huffman(symbol - 256, 7),
+        _ => writer.write_huffman(0b1100_0000 + symbol - 280, 8),
+    }
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest number of bytes for which b can't overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+    b << 16 | a
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    bit_count: u8,
+}
+
+impl BitWriter {
+    /// Writes the lowest 'count' bits of 'value', least significant bit first.
+    fn write_bits(&mut self, value: u32, count: u8) {
+        self.buffer |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting with their most significant bit.
+    fn write_huffman(&mut self, code: u32, length: u8) {
+        let reversed = code.reverse_bits() >> (32 - length as u32);
+        self.write_bits(reversed, length);
+    }
+
+    fn flush(&mut self) {
+        if self.bit_count > 0 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer = 0;
+            self.bit_count = 0;
+        }
+    }
+}